@@ -1,124 +1,1138 @@
-#![cfg(any(feature = "tcp", feature = "ssh"))]
+use crate::{
+    util::BytesToHex,
+    wrap::{read_transcript, split_engine, Direction, ReadHalf, WriteHalf},
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    future::Future,
+    ops::Range,
+    path::Path,
+    string::FromUtf8Error,
+    time::Duration,
+};
+use tokio::{
+    io::{
+        split, stdin, stdout, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, Error as IOError,
+        ErrorKind,
+    },
+    join, select,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    time::{sleep, timeout},
+};
+
+/// A remote stream that takes input.
+#[trait_variant::make(Send)]
+pub trait Engine: AsyncReadExt + AsyncWriteExt + Unpin + Sized {
+    const TIMEOUT: Duration;
+    const REPEAT: usize = 1;
+    /**
+    The line separator used by [`send_line`](Engine::send_line) and for splitting the output of
+    [`run_with_channel`](Engine::run_with_channel) into lines. Override it for CRLF targets.
+    */
+    const LINE_SEP: &'static [u8] = b"\n";
+
+    /// The chunk timeout this engine uses. Defaults to [`TIMEOUT`](Engine::TIMEOUT).
+    fn timeout(&self) -> Duration {
+        Self::TIMEOUT
+    }
+
+    /**
+    How many consecutive idle timeouts a chunk survives; the next one ends it, so a chunk ends after
+    `(repeat + 1) * timeout` of silence. Defaults to [`REPEAT`](Engine::REPEAT).
+    */
+    fn repeat(&self) -> usize {
+        Self::REPEAT
+    }
+
+    /**
+    How long [`drain_remaining`](Engine::drain_remaining) waits for more output once the last chunk
+    has ended. Defaults to ten times [`timeout`](Engine::timeout).
+    */
+    fn drain_timeout(&self) -> Duration {
+        self.timeout() * 10
+    }
+
+    /**
+    The effective [`timeout`](Engine::timeout) and [`repeat`](Engine::repeat) of this engine, for
+    working out why a chunk ended where it did.
+    */
+    fn timing(&self) -> (Duration, usize) {
+        (self.timeout(), self.repeat())
+    }
+
+    /// Reads the last chunk. See [`read_chunk`](Engine::read_chunk)
+    async fn read_last_chunk(&mut self) -> Result<String, EngineError> {
+        async { self.read_last_chunk_timeout(self.timeout()).await }
+    }
+
+    /**
+    Reads one "chunk" of remote input. A chunk "ends" when no new data is received for
+    [`timeout`](Engine::timeout) amount of time. This does not apply to the first byte read -- the
+    function will wait indefinitely until it receives *some* data from the remote stream.
+
+    Returns an error if the remote stream has already been closed, or if the chunk is not valid
+    UTF-8.
+    */
+    async fn read_chunk(&mut self) -> Result<String, EngineError> {
+        async { self.read_chunk_timeout(self.timeout()).await }
+    }
+
+    /// Like [`read_last_chunk`](Engine::read_last_chunk), but ends the chunk after `timeout`.
+    async fn read_last_chunk_timeout(&mut self, timeout: Duration) -> Result<String, EngineError> {
+        async move { Ok(String::from_utf8(self.read_last_chunk_bytes_timeout(timeout).await)?) }
+    }
+
+    /// Like [`read_chunk`](Engine::read_chunk), but ends the chunk after `timeout`.
+    async fn read_chunk_timeout(&mut self, timeout: Duration) -> Result<String, EngineError> {
+        async move { Ok(String::from_utf8(self.read_chunk_bytes_timeout(timeout).await?)?) }
+    }
+
+    /**
+    Like [`read_last_chunk`](Engine::read_last_chunk), but returns the raw bytes. A read error ends
+    the chunk like the stream closing does; use
+    [`read_last_chunk_with_end`](Engine::read_last_chunk_with_end) to tell the two apart.
+    */
+    async fn read_last_chunk_bytes(&mut self) -> Vec<u8> {
+        async { self.read_last_chunk_bytes_timeout(self.timeout()).await }
+    }
+
+    /**
+    Like [`read_chunk`](Engine::read_chunk), but returns the raw bytes. Nothing is decoded, so
+    binary output (pointers, null bytes) comes through intact.
+    */
+    async fn read_chunk_bytes(&mut self) -> Result<Vec<u8>, EngineError> {
+        async { self.read_chunk_bytes_timeout(self.timeout()).await }
+    }
+
+    /**
+    Like [`read_chunk`](Engine::read_chunk), but survives `repeat` idle timeouts instead of
+    [`repeat`](Engine::repeat) ones, for a remote that is known to pause mid-output.
+    */
+    async fn read_chunk_rounds(&mut self, repeat: usize) -> Result<String, EngineError> {
+        async move { Ok(String::from_utf8(self.read_chunk_bytes_rounds(repeat).await?)?) }
+    }
+
+    /// Like [`read_chunk_rounds`](Engine::read_chunk_rounds), but returns the raw bytes.
+    async fn read_chunk_bytes_rounds(&mut self, repeat: usize) -> Result<Vec<u8>, EngineError> {
+        async move { self.read_chunk_stable(repeat + 1, self.timeout()).await }
+    }
+
+    /**
+    Like [`read_chunk`](Engine::read_chunk), but only ends the chunk at the end of a line: if the
+    data read so far does not end in [`LINE_SEP`](Engine::LINE_SEP) when the chunk times out, it
+    keeps waiting for more chunks until it does. A prompt without a line break therefore never ends
+    one; use [`recv_until`](Engine::recv_until) for those.
+    */
+    async fn read_line_chunk(&mut self) -> Result<String, EngineError> {
+        async { Ok(String::from_utf8(self.read_line_chunk_bytes().await?)?) }
+    }
+
+    /**
+    Like [`read_line_chunk`](Engine::read_line_chunk), but returns the raw bytes. If the stream
+    closes mid-line, the [`Eof`](EngineError::Eof) error carries everything read.
+    */
+    async fn read_line_chunk_bytes(&mut self) -> Result<Vec<u8>, EngineError> {
+        async {
+            let mut buf = self.read_chunk_bytes().await?;
+            while !buf.ends_with(Self::LINE_SEP) {
+                match self.read_chunk_bytes().await {
+                    Ok(mut more) => buf.append(&mut more),
+                    Err(EngineError::Eof(mut partial)) => {
+                        buf.append(&mut partial);
+                        return Err(EngineError::Eof(buf));
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
+            Ok(buf)
+        }
+    }
+
+    /// Like [`read_last_chunk_bytes`](Engine::read_last_chunk_bytes), but ends the chunk after
+    /// `timeout`.
+    async fn read_last_chunk_bytes_timeout(&mut self, timeout: Duration) -> Vec<u8> {
+        async move { read_idle(self, self.repeat() + 1, timeout).await.data }
+    }
+
+    /**
+    Like [`read_last_chunk_bytes`](Engine::read_last_chunk_bytes), but also reports whether the
+    chunk ended because the remote went quiet, closed the stream or failed.
+    */
+    async fn read_last_chunk_with_end(&mut self) -> Chunk {
+        async { read_idle(self, self.repeat() + 1, self.timeout()).await }
+    }
+
+    /**
+    Like [`read_chunk_bytes`](Engine::read_chunk_bytes), but also reports how the chunk ended. A
+    stream that is already closed gives an empty [`Eof`](ChunkEnd::Eof) chunk instead of an error,
+    and a failing one an empty [`Error`](ChunkEnd::Error) chunk.
+    */
+    async fn read_chunk_with_end(&mut self) -> Chunk {
+        async {
+            match self.read_u8().await {
+                Ok(b) => {
+                    let mut chunk = self.read_last_chunk_with_end().await;
+                    chunk.data.insert(0, b);
+                    chunk
+                }
+                Err(error) if error.kind() == ErrorKind::UnexpectedEof => {
+                    Chunk { data: Vec::new(), ended: ChunkEnd::Eof }
+                }
+                Err(error) => Chunk { data: Vec::new(), ended: ChunkEnd::Error(error.kind()) },
+            }
+        }
+    }
+
+    /**
+    Like [`read_chunk_bytes`](Engine::read_chunk_bytes), but with the end of the chunk spelled out:
+    after the first byte, the chunk ends once `idle_rounds` consecutive rounds of `per_round` pass
+    without any data, or when the stream closes. Any byte resets the count.
+    */
+    async fn read_chunk_stable(
+        &mut self,
+        idle_rounds: usize,
+        per_round: Duration,
+    ) -> Result<Vec<u8>, EngineError> {
+        async move {
+            let mut buf = vec![self.read_u8().await?];
+            buf.append(&mut read_idle(self, idle_rounds, per_round).await.into_data()?);
+            Ok(buf)
+        }
+    }
+
+    /**
+    Reads everything the remote process still has to say: the last chunk, then anything else that
+    arrives before the stream closes or goes quiet for [`drain_timeout`](Engine::drain_timeout).
+    Use this after the final write, where the chunk heuristic alone tends to cut the tail off.
+    */
+    async fn drain_remaining(&mut self) -> Vec<u8> {
+        async {
+            let mut buf = self.read_last_chunk_bytes().await;
+            buf.append(&mut read_idle(self, 1, self.drain_timeout()).await.data);
+            buf
+        }
+    }
+
+    /**
+    Iterates over the chunks the remote stream sends, one per
+    [`read_chunk_bytes`](Engine::read_chunk_bytes), until it closes.
+
+    ```no_run
+    use engine::{tcp, Engine};
+
+    # #[tokio::main]
+    # async fn main() {
+    let mut handle = tcp("www.example.com:65535").await.unwrap();
+    let mut chunks = handle.chunks();
+    while let Some(chunk) = chunks.next().await {
+        println!("{:?}", chunk.unwrap());
+    }
+    # }
+    ```
+    */
+    fn chunks(&mut self) -> Chunks<'_, Self> {
+        Chunks { engine: self, done: false }
+    }
+
+    /// Like [`read_chunk_bytes`](Engine::read_chunk_bytes), but ends the chunk after `timeout`.
+    async fn read_chunk_bytes_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, EngineError> {
+        async move {
+            let mut buf = vec![self.read_u8().await?];
+            buf.append(&mut read_idle(self, self.repeat() + 1, timeout).await.into_data()?);
+            Ok(buf)
+        }
+    }
+
+    /**
+    Reads from the remote stream until the data read so far ends with `delim`. Returns everything
+    that was read, *including* `delim`.
+
+    Unlike [`read_chunk`](Engine::read_chunk), this never gives up because of
+    [`TIMEOUT`](Engine::TIMEOUT) -- it waits for as long as it takes for `delim` to arrive. If the
+    stream ends first, an [`Eof`](EngineError::Eof) error is returned that carries the bytes read up
+    to that point.
+    */
+    async fn recv_until(&mut self, delim: &[u8]) -> Result<Vec<u8>, EngineError> {
+        async move { self.recv_until_max(delim, usize::MAX).await }
+    }
+
+    /**
+    Like [`recv_until`](Engine::recv_until), but gives up once `max` bytes have been read without
+    finding `delim`. In that case a [`DelimiterNotFound`](EngineError::DelimiterNotFound) error is
+    returned that carries those `max` bytes.
+    */
+    async fn recv_until_max(&mut self, delim: &[u8], max: usize) -> Result<Vec<u8>, EngineError> {
+        async move {
+            let mut buf = Vec::new();
+            while !buf.ends_with(delim) {
+                if buf.len() == max {
+                    return Err(EngineError::DelimiterNotFound(buf));
+                }
+                match self.read_u8().await {
+                    Ok(b) => buf.push(b),
+                    Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                        return Err(EngineError::Eof(buf))
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Ok(buf)
+        }
+    }
+
+    /**
+    Like [`recv_until`](Engine::recv_until), but leaves `delim` off the end of the result. The
+    delimiter is still consumed.
+    */
+    async fn recv_before(&mut self, delim: &[u8]) -> Result<Vec<u8>, EngineError> {
+        async move {
+            let mut buf = self.recv_until(delim).await?;
+            buf.truncate(buf.len() - delim.len());
+            Ok(buf)
+        }
+    }
+
+    /**
+    Waits up to `dur` for the remote to send something and returns whatever arrives in one read,
+    or [`None`] if the remote stayed silent. Unlike the chunk readers, a timeout is reported rather
+    than treated as the end of a chunk. Fails with [`Eof`](EngineError::Eof) if the stream has been
+    closed.
+    */
+    async fn recv_timeout(&mut self, dur: Duration) -> Result<Option<Vec<u8>>, EngineError> {
+        async move {
+            let mut buf = vec![0; 4096];
+            match timeout(dur, self.read(&mut buf)).await {
+                Err(_) => Ok(None),
+                Ok(Ok(0)) => Err(EngineError::Eof(Vec::new())),
+                Ok(Ok(n)) => {
+                    buf.truncate(n);
+                    Ok(Some(buf))
+                }
+                Ok(Err(e)) => Err(e.into()),
+            }
+        }
+    }
+
+    /**
+    Reads one line and returns it without its [`LINE_SEP`](Engine::LINE_SEP) terminator. Errors
+    like [`recv_until`](Engine::recv_until) if the stream ends first.
+    */
+    async fn recv_line(&mut self) -> Result<Vec<u8>, EngineError> {
+        async {
+            let mut line = self.recv_line_keep().await?;
+            line.truncate(line.len() - Self::LINE_SEP.len());
+            Ok(line)
+        }
+    }
+
+    /// Like [`recv_line`](Engine::recv_line), but keeps the terminator.
+    async fn recv_line_keep(&mut self) -> Result<Vec<u8>, EngineError> {
+        async { self.recv_until(Self::LINE_SEP).await }
+    }
+
+    /**
+    Like [`recv_until`](Engine::recv_until), but stops as soon as the data read so far ends with
+    any of `delims`. Returns everything that was read along with the index in `delims` of the
+    delimiter that matched; if several match at once, the first one listed wins.
+    */
+    async fn recv_until_any(&mut self, delims: &[&[u8]]) -> Result<(Vec<u8>, usize), EngineError> {
+        async move {
+            let mut buf = Vec::new();
+            loop {
+                if let Some(index) = delims.iter().position(|delim| buf.ends_with(delim)) {
+                    return Ok((buf, index));
+                }
+                match self.read_u8().await {
+                    Ok(b) => buf.push(b),
+                    Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                        return Err(EngineError::Eof(buf))
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+    }
+
+    /**
+    Reads until the remote stream is closed, returning everything it sent. Use this for targets
+    that print all of their output and exit, where [`read_last_chunk`](Engine::read_last_chunk)
+    would either end too early or idle needlessly at the end.
+    */
+    async fn recv_all(&mut self) -> Result<Vec<u8>, EngineError> {
+        async {
+            let mut buf = Vec::new();
+            self.read_to_end(&mut buf).await?;
+            Ok(buf)
+        }
+    }
+
+    /**
+    Reads from the remote stream until `matcher` finds something in the data read so far. Returns
+    everything that was read along with the range that `matcher` reported.
+
+    `matcher` is called with the whole buffer after every byte, so this works with any pattern
+    engine. With [`regex`](https://docs.rs/regex), grabbing a leaked address looks like:
+    ```ignore
+    let re = regex::bytes::Regex::new(r"0x([0-9a-f]+)\n").unwrap();
+    let (buf, leak) = handle
+        .recv_match(|buf| re.captures(buf).and_then(|c| c.get(1)).map(|m| m.range()))
+        .await?;
+    let leak = &buf[leak];
+    ```
+    If the stream ends first, an [`Eof`](EngineError::Eof) error is returned that carries the bytes
+    read up to that point. With the `regex` feature, [`recv_regex`](Engine::recv_regex) and
+    [`recv_regex_group`](Engine::recv_regex_group) cover the common cases.
+    */
+    async fn recv_match<F>(
+        &mut self,
+        mut matcher: F,
+    ) -> Result<(Vec<u8>, Range<usize>), EngineError>
+    where
+        F: FnMut(&[u8]) -> Option<Range<usize>> + Send,
+    {
+        async move {
+            let mut buf = Vec::new();
+            loop {
+                match self.read_u8().await {
+                    Ok(b) => buf.push(b),
+                    Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                        return Err(EngineError::Eof(buf))
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+                if let Some(range) = matcher(&buf) {
+                    return Ok((buf, range));
+                }
+            }
+        }
+    }
+
+    /**
+    Reads from the remote stream until `re` matches somewhere in the data read so far, and returns
+    everything that was read. The buffer is checked after every byte, so a pattern matches as soon
+    as it can: end it with a terminator (e.g. `\n`) when the tail of the match matters. Errors
+    like [`recv_match`](Engine::recv_match) if the stream ends first.
+
+    ```no_run
+    use engine::{tcp, Engine};
+    use regex::bytes::Regex;
+
+    # #[tokio::main]
+    # async fn main() {
+    let mut handle = tcp("www.example.com:65535").await.unwrap();
+    let output = handle.recv_regex(&Regex::new(r"0x[0-9a-f]+\n").unwrap()).await.unwrap();
+    # }
+    ```
+    */
+    #[cfg(feature = "regex")]
+    async fn recv_regex(&mut self, re: &regex::bytes::Regex) -> Result<Vec<u8>, EngineError> {
+        async move { Ok(self.recv_match(|buf| re.find(buf).map(|m| m.range())).await?.0) }
+    }
+
+    /**
+    Like [`recv_regex`](Engine::recv_regex), but returns only the bytes of the first capture group
+    of the match, e.g. the digits of a leaked address. A pattern without a capture group never
+    matches.
+    */
+    #[cfg(feature = "regex")]
+    async fn recv_regex_group(&mut self, re: &regex::bytes::Regex) -> Result<Vec<u8>, EngineError> {
+        async move {
+            let (buf, group) =
+                self.recv_match(|buf| re.captures(buf)?.get(1).map(|m| m.range())).await?;
+            Ok(buf[group].to_vec())
+        }
+    }
+
+    /**
+    Reads exactly `n` bytes from the remote stream. The bytes are returned as-is, so this is safe
+    to use on binary data such as leaked addresses.
+
+    If the stream ends before `n` bytes arrive, an [`Eof`](EngineError::Eof) error is returned that
+    carries the bytes read so far.
+    */
+    async fn recv_n(&mut self, n: usize) -> Result<Vec<u8>, EngineError> {
+        async move {
+            let mut buf = vec![0; n];
+            let mut filled = 0;
+            while filled < n {
+                match self.read(&mut buf[filled..]).await? {
+                    0 => {
+                        buf.truncate(filled);
+                        return Err(EngineError::Eof(buf));
+                    }
+                    read => filled += read,
+                }
+            }
+            Ok(buf)
+        }
+    }
+
+    /// Writes `data` followed by [`LINE_SEP`](Engine::LINE_SEP), then flushes.
+    async fn send_line(&mut self, data: &[u8]) -> Result<(), IOError> {
+        async move {
+            self.write_all(&[data, Self::LINE_SEP].concat()).await?;
+            self.flush().await
+        }
+    }
+
+    /// Sends `n` in decimal as a line (see [`send_line`](Engine::send_line)), e.g. a menu choice.
+    async fn send_int(&mut self, n: impl Into<i128> + Send) -> Result<(), IOError> {
+        async move { self.send_line(n.into().to_string().as_bytes()).await }
+    }
+
+    /// Sends `data` as a line of lowercase hex digits (see [`send_line`](Engine::send_line)).
+    async fn send_hex(&mut self, data: &[u8]) -> Result<(), IOError> {
+        async move { self.send_line(data.to_hex().await.as_bytes()).await }
+    }
+
+    /**
+    Writes `data` one byte at a time, flushing after each byte and sleeping for `delay` between
+    them. For parsers that mishandle input arriving all at once, or to spread input out in time.
+    */
+    async fn send_slow(&mut self, data: &[u8], delay: Duration) -> Result<(), IOError> {
+        async move { self.send_slow_chunks(data, 1, delay).await }
+    }
+
+    /**
+    Like [`send_slow`](Engine::send_slow), but writes `chunk_size` bytes at a time (the last chunk
+    may be shorter). Panics if `chunk_size` is zero.
+    */
+    async fn send_slow_chunks(
+        &mut self,
+        data: &[u8],
+        chunk_size: usize,
+        delay: Duration,
+    ) -> Result<(), IOError> {
+        async move {
+            for (i, chunk) in data.chunks(chunk_size).enumerate() {
+                if i > 0 {
+                    sleep(delay).await;
+                }
+                self.write_all(chunk).await?;
+                self.flush().await?;
+            }
+            Ok(())
+        }
+    }
+
+    /**
+    Waits for `delim` (see [`recv_until`](Engine::recv_until)), then writes `data`. Returns
+    everything that was read while waiting.
+    */
+    async fn send_after(&mut self, delim: &[u8], data: &[u8]) -> Result<Vec<u8>, EngineError> {
+        async move {
+            let received = self.recv_until(delim).await?;
+            self.write_all(data).await?;
+            self.flush().await?;
+            Ok(received)
+        }
+    }
+
+    /// Like [`send_after`](Engine::send_after), but writes `data` with
+    /// [`send_line`](Engine::send_line).
+    async fn send_line_after(&mut self, delim: &[u8], data: &[u8]) -> Result<Vec<u8>, EngineError> {
+        async move {
+            let received = self.recv_until(delim).await?;
+            self.send_line(data).await?;
+            Ok(received)
+        }
+    }
+
+    /**
+    Reads and returns whatever the remote stream has already sent, stopping once `within` passes
+    without any new data. Unlike [`read_chunk`](Engine::read_chunk), this does not wait for a first
+    byte: if there is nothing to read, it returns an empty vector right away.
+    */
+    async fn clean(&mut self, within: Duration) -> Vec<u8> {
+        async move {
+            let mut cleaned = read_idle(self, 1, Duration::ZERO).await.data;
+            if !cleaned.is_empty() {
+                cleaned.append(&mut read_idle(self, 1, within).await.data);
+            }
+            cleaned
+        }
+    }
+
+    /**
+    Returns whatever the remote stream has already sent, without waiting at all: an empty vector
+    if nothing is buffered. Like pwntools' `recv(timeout=0)`.
+    */
+    async fn recv_available(&mut self) -> Vec<u8> {
+        async { self.clean(Duration::ZERO).await }
+    }
+
+    /**
+    Hands the remote stream over to the terminal: everything read from [`stdin`] is forwarded to
+    the remote stream, and everything the remote stream sends is written to [`stdout`]. Returns
+    once either side reaches EOF. This can follow [`run`](Engine::run), which leaves the write side
+    open, but not [`finish`](Engine::finish) or [`run_and_finish`](Engine::run_and_finish).
+
+    If [`stdin`] is a terminal, it is switched to non-canonical mode without echo for the duration,
+    so keystrokes are forwarded as they are typed and only the remote's own echo shows up. The
+    terminal is restored however this returns, but not if the process is killed midway (e.g. by
+    Ctrl-C); `stty sane` fixes that.
+    */
+    async fn interactive(&mut self) -> Result<(), EngineError> {
+        async {
+            let _raw = RawStdin::enable();
+            let (mut reader, mut writer) = split(self);
+            let outgoing = async {
+                let mut stdin = stdin();
+                while forward(&mut stdin, &mut writer).await? > 0 {}
+                Ok::<(), IOError>(())
+            };
+            let incoming = async {
+                let mut stdout = stdout();
+                while forward(&mut reader, &mut stdout).await? > 0 {}
+                Ok::<(), IOError>(())
+            };
+            select! {
+                r = outgoing => r?,
+                r = incoming => r?,
+            }
+            Ok(())
+        }
+    }
+
+    /**
+    Reads once from [`stdin`] and writes what was read to the remote stream. Returns how many bytes
+    were forwarded, which is 0 once [`stdin`] reaches EOF.
+
+    Together with [`forward_stdout`](Engine::forward_stdout), this is one step of
+    [`interactive`](Engine::interactive), for interaction loops that also need to react to
+    something else:
+
+    ```no_run
+    use engine::{tcp, Engine};
+    use tokio::{select, sync::mpsc::unbounded_channel};
+
+    # #[tokio::main]
+    # async fn main() {
+    let mut handle = tcp("www.example.com:65535").await.unwrap();
+    let (_commands, mut rx) = unbounded_channel::<Vec<u8>>();
+    loop {
+        select! {
+            n = handle.forward_stdout() => if n.unwrap() == 0 { break },
+            Some(command) = rx.recv() => handle.send_line(&command).await.unwrap(),
+        }
+    }
+    # }
+    ```
+
+    Data already read but not yet written is lost if the future is dropped midway (e.g. by losing a
+    `select!`), so prefer not to race these against streams that fire often.
+    */
+    async fn forward_stdin(&mut self) -> Result<usize, EngineError> {
+        async { Ok(forward(&mut stdin(), self).await?) }
+    }
+
+    /**
+    Reads once from the remote stream and writes what was read to [`stdout`]. Returns how many bytes
+    were forwarded, which is 0 once the remote stream reaches EOF. See
+    [`forward_stdin`](Engine::forward_stdin).
+    */
+    async fn forward_stdout(&mut self) -> Result<usize, EngineError> {
+        async { Ok(forward(self, &mut stdout()).await?) }
+    }
+
+    /**
+    Splits the engine into a reading and a writing half, like [`tokio::io::split`], so that the two
+    directions can be driven from different tasks. Each half is an [`Engine`] with this engine's
+    timing; use read methods on the [`ReadHalf`] and send methods on the [`WriteHalf`].
+    [`ReadHalf::unsplit`] puts them back together.
+
+    ```no_run
+    use engine::{listen, Engine};
+
+    # #[tokio::main]
+    # async fn main() {
+    let (mut reader, mut writer) = listen("0.0.0.0:4444").await.unwrap().split();
+    tokio::spawn(async move {
+        while let Ok(chunk) = reader.read_chunk_bytes().await {
+            print!("{}", String::from_utf8_lossy(&chunk));
+        }
+    });
+    writer.send_line(b"id").await.unwrap();
+    # }
+    ```
+    */
+    fn split(self) -> (ReadHalf<Self>, WriteHalf<Self>) {
+        split_engine(self)
+    }
+
+    /**
+    Like [`run`](Engine::run), but reports the conversation over an
+    [unbounded channel](tokio::sync::mpsc::unbounded_channel) as it happens: every input as an
+    [`Event::Sent`], right after it was written, and the remote output as [`Event::Recv`]s, one line
+    at a time. Lines keep their [`LINE_SEP`](Engine::LINE_SEP) terminator, so a line without one is
+    the incomplete tail of a chunk. The events are in the order things happened, so the input each
+    prompt got is the [`Sent`](Event::Sent) that follows it.
+    */
+    fn run_with_channel<I>(
+        &mut self,
+        input: I,
+    ) -> (UnboundedReceiver<Event>, impl Future<Output = Result<(), EngineError>> + Send)
+    where
+        I: IntoIterator + Send,
+        <I as IntoIterator>::IntoIter: Send,
+        <I as IntoIterator>::Item: AsRef<[u8]> + Send,
+    {
+        exchange(self, input, true)
+    }
+
+    /**
+    Like [`run_with_channel`](Engine::run_with_channel), but nothing is echoed to stdout; the
+    channel is the only record of the conversation.
+    */
+    fn run_silent<I>(
+        &mut self,
+        input: I,
+    ) -> (UnboundedReceiver<Event>, impl Future<Output = Result<(), EngineError>> + Send)
+    where
+        I: IntoIterator + Send,
+        <I as IntoIterator>::IntoIter: Send,
+        <I as IntoIterator>::Item: AsRef<[u8]> + Send,
+    {
+        exchange(self, input, false)
+    }
+
+    /**
+    Executes a series of transactions as such:
+    1. Wait for data from the remote stream (see [`read_chunk`](Engine::read_chunk))
+    2. Pops one item from the top of `input` and writes it to the remote stream. Items can be
+       anything that is [`AsRef<[u8]>`](AsRef), so owned payloads built at runtime (`Vec<u8>`)
+       work as well as borrowed ones.
+    3. Repeat.
+
+    Once `input` runs out, the remaining output is collected with
+    [`drain_remaining`](Engine::drain_remaining). Every input is flushed as it is written, but `run`
+    does not call [`finish`](Engine::finish): the write side is left open, so the conversation can
+    go on afterwards, e.g. by handing it to [`interactive`](Engine::interactive). Use
+    [`run_and_finish`](Engine::run_and_finish) when the remote should see EOF once `input` is done.
+    */
+    async fn run<I>(&mut self, input: I) -> Result<(), EngineError>
+    where
+        I: IntoIterator + Send,
+        <I as IntoIterator>::IntoIter: Send,
+        <I as IntoIterator>::Item: AsRef<[u8]> + Send,
+    {
+        async move { self.run_with_channel(input).1.await }
+    }
+
+    /**
+    Like [`run`](Engine::run), but then closes the write side with [`finish`](Engine::finish), so
+    that the last input is sure to be delivered and the remote sees EOF.
+    */
+    async fn run_and_finish<I>(&mut self, input: I) -> Result<(), EngineError>
+    where
+        I: IntoIterator + Send,
+        <I as IntoIterator>::IntoIter: Send,
+        <I as IntoIterator>::Item: AsRef<[u8]> + Send,
+    {
+        async move {
+            self.run(input).await?;
+            Ok(self.finish().await?)
+        }
+    }
+
+    /**
+    Like [`run`](Engine::run), but gives up with a [`Timeout`](EngineError::Timeout) error once the
+    whole interaction has taken longer than `total`. The chunk timeouts only bound the silence
+    between chunks, so this is what keeps a target that never ends a chunk from hanging forever.
+    */
+    async fn run_timeout<I>(&mut self, input: I, total: Duration) -> Result<(), EngineError>
+    where
+        I: IntoIterator + Send,
+        <I as IntoIterator>::IntoIter: Send,
+        <I as IntoIterator>::Item: AsRef<[u8]> + Send,
+    {
+        async move { timeout(total, self.run(input)).await.map_err(|_| EngineError::Timeout)? }
+    }
+
+    /**
+    Flushes anything still buffered on the way to the remote stream, then shuts down the write side
+    so the remote sees EOF. Reads still work afterwards, for whatever the remote says last.
+    */
+    async fn finish(&mut self) -> Result<(), IOError> {
+        async {
+            self.flush().await?;
+            self.shutdown().await
+        }
+    }
 
-use std::{error::Error, future::Future, string::FromUtf8Error, time::Duration};
-use tokio::{
-    io::{stdout, AsyncReadExt, AsyncWriteExt, Error as IOError},
-    join,
-    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
-    time::timeout,
-};
+    /**
+    Replays a transcript written by [`Logged::record`](crate::wrap::Logged::record) against this
+    engine: the data that was sent is sent again, in order, each time after waiting for the prompt
+    that preceded it in the transcript. Returns everything received along the way, including what
+    follows the last input (see [`drain_remaining`](Engine::drain_remaining)).
 
-/// A remote stream that takes input.
-#[trait_variant::make(Send)]
-pub trait Engine: AsyncReadExt + AsyncWriteExt + Unpin + Sized {
-    const TIMEOUT: Duration;
-    const REPEAT: usize = 1;
+    The prompt is the last line received before an input. Earlier output is not compared at all, so
+    it may differ from the transcript (banners with PIDs or timestamps in them, say). See
+    [`replay_with`](Engine::replay_with) for prompts that differ too.
+    */
+    async fn replay(
+        &mut self,
+        transcript: impl AsRef<Path> + Send,
+    ) -> Result<Vec<u8>, EngineError> {
+        async move { self.replay_with(transcript, usize::MAX).await }
+    }
 
-    /// Reads the last chunk. See [`read_chunk`](Engine::read_chunk)
-    async fn read_last_chunk(&mut self) -> Result<String, FromUtf8Error> {
-        async {
-            let mut buf = Vec::new();
-            let mut dropped = vec![false; Self::REPEAT];
-            'a: loop {
-                match timeout(Self::TIMEOUT, self.read_u8()).await {
-                    Ok(Ok(b)) => {
-                        dropped = vec![false; Self::REPEAT];
-                        buf.push(b);
-                    }
-                    _ => {
-                        for i in 0..Self::REPEAT {
-                            if !dropped[i] {
-                                dropped[i] = true;
-                                continue 'a;
-                            }
+    /**
+    Like [`replay`](Engine::replay), but only waits for the last `prompt_len` bytes of each prompt.
+    With a `prompt_len` of 0, each input is sent after the next [chunk](Engine::read_chunk_bytes)
+    instead.
+    */
+    async fn replay_with(
+        &mut self,
+        transcript: impl AsRef<Path> + Send,
+        prompt_len: usize,
+    ) -> Result<Vec<u8>, EngineError> {
+        async move {
+            let (mut received, mut expected) = (Vec::new(), Vec::new());
+            for entry in read_transcript(transcript)? {
+                if entry.direction == Direction::Recv {
+                    expected.extend_from_slice(&entry.data);
+                    continue;
+                }
+
+                if !expected.is_empty() {
+                    let read = match prompt(&expected, Self::LINE_SEP, prompt_len) {
+                        [] => self.read_chunk_bytes().await,
+                        prompt => self.recv_until(prompt).await,
+                    };
+                    match read {
+                        Ok(mut data) => received.append(&mut data),
+                        Err(EngineError::Eof(mut partial)) => {
+                            received.append(&mut partial);
+                            return Err(EngineError::Eof(received));
                         }
-                        return Ok(String::from_utf8(buf)?);
+                        Err(error) => return Err(error),
                     }
+                    expected.clear();
                 }
+                self.write_all(&entry.data).await?;
+                self.flush().await?;
             }
+            received.append(&mut self.drain_remaining().await);
+            Ok(received)
         }
     }
+}
 
+/**
+The error type of the read and run methods of [`Engine`]. Errors that end a read early carry the
+bytes that had already been received.
+*/
+#[derive(Debug)]
+pub enum EngineError {
+    /// The underlying stream failed.
+    Io(IOError),
+    /// A chunk was not valid UTF-8.
+    Utf8(FromUtf8Error),
+    /// The remote stream ended before the read was complete. Carries the bytes read until then.
+    Eof(Vec<u8>),
+    /// The operation did not finish in time.
+    Timeout,
+    /// The delimiter did not show up within the allowed number of bytes. Carries those bytes.
+    DelimiterNotFound(Vec<u8>),
     /**
-    Reads one "chunk" of remote input. A chunk "ends" when no new data is received for
-    [`TIMEOUT`](Engine::TIMEOUT) amount of time. This does not apply to the first byte read -- the
-    function will wait indefinitely until it receives *some* data from the remote stream.
+    The remote end went away while it was being written to ([`BrokenPipe`](ErrorKind::BrokenPipe)
+    or [`ConnectionReset`](ErrorKind::ConnectionReset)). When the payload was meant to crash the
+    target, this is usually the sign that it worked.
     */
-    async fn read_chunk(&mut self) -> Result<String, FromUtf8Error> {
-        async {
-            Ok(String::from_utf8(vec![self.read_u8().await.unwrap()])?
-                + &self.read_last_chunk().await?)
+    RemoteClosed(IOError),
+}
+impl Display for EngineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "I/O error: {}", error),
+            Self::Utf8(error) => write!(f, "invalid UTF-8: {}", error),
+            Self::Eof(partial) => write!(f, "stream ended after {} bytes", partial.len()),
+            Self::Timeout => write!(f, "timed out"),
+            Self::DelimiterNotFound(partial) => {
+                write!(f, "delimiter not found in {} bytes", partial.len())
+            }
+            Self::RemoteClosed(error) => write!(f, "remote end closed: {}", error),
         }
     }
-
+}
+impl Error for EngineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) | Self::RemoteClosed(error) => Some(error),
+            Self::Utf8(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+impl From<IOError> for EngineError {
     /**
-    Like [`run`](Engine::run), but forwards input received from the remote process over an
-    [unbounded channel](tokio::sync::mpsc::unbounded_channel).
+    An [`UnexpectedEof`](ErrorKind::UnexpectedEof) error becomes an empty [`Eof`](Self::Eof), and a
+    [`BrokenPipe`](ErrorKind::BrokenPipe) or [`ConnectionReset`](ErrorKind::ConnectionReset) error
+    becomes [`RemoteClosed`](Self::RemoteClosed). This also lets the errors of the write methods be
+    classified with `EngineError::from`.
     */
-    fn run_with_channel<'a, I>(
-        &mut self,
-        input: I,
-    ) -> (
-        UnboundedReceiver<String>,
-        impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send,
-    )
-    where
-        I: IntoIterator<Item = &'a [u8]> + Send,
-        <I as IntoIterator>::IntoIter: Send,
-    {
-        let (sender, receiver) = unbounded_channel();
-        let future = async move {
-            let mut stdout = stdout();
-            for i in input {
-                write(self.read_chunk().await?, Some(&sender)).await?;
-                let (r1, r2) = join!(self.write_all(i), async {
-                    stdout.write_all(i).await?;
-                    stdout.write_u8(b'\n').await?;
-                    Ok::<(), IOError>(())
-                });
-                r1?;
-                r2?;
-            }
+    fn from(error: IOError) -> Self {
+        match error.kind() {
+            ErrorKind::UnexpectedEof => Self::Eof(Vec::new()),
+            ErrorKind::BrokenPipe | ErrorKind::ConnectionReset => Self::RemoteClosed(error),
+            _ => Self::Io(error),
+        }
+    }
+}
+impl From<FromUtf8Error> for EngineError {
+    fn from(error: FromUtf8Error) -> Self {
+        Self::Utf8(error)
+    }
+}
 
-            let chunk = self.read_last_chunk().await?;
-            for string in chunk.split("\n") {
-                if !sender.is_closed() {
-                    sender.send(string.to_owned()).unwrap();
-                }
+/**
+The last line of `received`, up to its last `max` bytes: what [`replay`](Engine::replay) waits for.
+A line that ends in `separator` keeps it, so that a prompt ending in a line break is still found.
+*/
+fn prompt<'r>(received: &'r [u8], separator: &[u8], max: usize) -> &'r [u8] {
+    let body = received.strip_suffix(separator).unwrap_or(received);
+    let start = match separator.len() {
+        0 => 0,
+        n => body.windows(n).rposition(|w| w == separator).map_or(0, |i| i + n),
+    };
+    let line = &received[start..];
+    &line[line.len().saturating_sub(max)..]
+}
+
+/// Writes the result of one read from `from` to `to` and flushes it. Returns the bytes copied.
+async fn forward(
+    from: &mut (impl AsyncRead + Unpin),
+    to: &mut (impl AsyncWrite + Unpin),
+) -> Result<usize, IOError> {
+    let mut buf = [0; 1024];
+    let n = from.read(&mut buf).await?;
+    if n > 0 {
+        to.write_all(&buf[..n]).await?;
+        to.flush().await?;
+    }
+    Ok(n)
+}
+
+/**
+Keeps the terminal on stdin in non-canonical mode without echo for as long as it lives, for
+[`interactive`](Engine::interactive). Holds the settings to restore, or [`None`] if stdin is not a
+terminal and was left alone.
+*/
+#[cfg(unix)]
+struct RawStdin(Option<libc::termios>);
+
+#[cfg(unix)]
+impl RawStdin {
+    fn enable() -> Self {
+        // SAFETY: `termios` is plain data, and stdin stays open for the duration of these calls.
+        unsafe {
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::isatty(libc::STDIN_FILENO) != 1
+                || libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0
+            {
+                return Self(None);
             }
-            stdout.write_all(chunk.as_bytes()).await?;
-            Ok(())
-        };
-        (receiver, future)
+            let original = termios;
+            termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+            termios.c_cc[libc::VMIN] = 1;
+            termios.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) != 0 {
+                return Self(None);
+            }
+            Self(Some(original))
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawStdin {
+    fn drop(&mut self) {
+        if let Some(original) = &self.0 {
+            // SAFETY: as in `enable`.
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original) };
+        }
+    }
+}
+
+/// Elsewhere the terminal is left as it is.
+#[cfg(not(unix))]
+struct RawStdin;
+
+#[cfg(not(unix))]
+impl RawStdin {
+    fn enable() -> Self {
+        Self
+    }
+}
+
+/**
+Reads until `idle_rounds` consecutive reads time out after `per_round`, or the stream closes or
+fails. Each read takes whatever is available, up to [`READ_BLOCK`] bytes or the spare capacity of
+the buffer.
+*/
+async fn read_idle<E: Engine>(engine: &mut E, idle_rounds: usize, per_round: Duration) -> Chunk {
+    let (mut data, mut idle) = (Vec::new(), 0);
+    while idle < idle_rounds {
+        data.reserve(READ_BLOCK);
+        match timeout(per_round, engine.read_buf(&mut data)).await {
+            Ok(Ok(0)) => return Chunk { data, ended: ChunkEnd::Eof },
+            Ok(Err(error)) => return Chunk { data, ended: ChunkEnd::Error(error.kind()) },
+            Ok(Ok(_)) => idle = 0,
+            Err(_) => idle += 1,
+        }
+    }
+    Chunk { data, ended: ChunkEnd::Idle }
+}
+
+/// The least free space [`read_idle`] makes in its buffer before each read.
+const READ_BLOCK: usize = 4096;
+
+/// A chunk of remote input, as returned by [`read_chunk_with_end`](Engine::read_chunk_with_end).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    /// The bytes received.
+    pub data: Vec<u8>,
+    /// Why the chunk ended.
+    pub ended: ChunkEnd,
+}
+
+impl Chunk {
+    /// The data, or the error the chunk [ended](ChunkEnd::Error) in.
+    fn into_data(self) -> Result<Vec<u8>, EngineError> {
+        match self.ended {
+            ChunkEnd::Error(kind) => Err(IOError::from(kind).into()),
+            _ => Ok(self.data),
+        }
     }
+}
+
+/// One step of a conversation, as reported by [`run_with_channel`](Engine::run_with_channel).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A line of output received from the remote stream.
+    Recv(Vec<u8>),
+    /// An input that was written to the remote stream.
+    Sent(Vec<u8>),
+}
+
+/// How a [`Chunk`] ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkEnd {
+    /// The remote stopped sending for long enough; it is probably waiting for input.
+    Idle,
+    /// The remote stream was closed, e.g. because the process exited or crashed.
+    Eof,
+    /// Reading failed with an I/O error of this kind, e.g. a reset connection.
+    Error(ErrorKind),
+}
+
+/**
+The chunks of an [`Engine`], as returned by [`chunks`](Engine::chunks). This is an async iterator
+rather than a `Stream`, so that the API does not depend on `futures`.
+*/
+pub struct Chunks<'a, E> {
+    engine: &'a mut E,
+    done: bool,
+}
 
+impl<E: Engine> Chunks<'_, E> {
     /**
-    Executes a series of transactions as such:
-    1. Wait for data from the remote stream (see [`read_chunk`](Engine::read_chunk))
-    2. Pops one `&[u8]` from the top of `input` and writes it to the remote stream.
-    3. Repeat.
+    Reads the next chunk. Returns [`None`] once the stream has closed, after yielding whatever
+    arrived just before it did. Any other error is yielded once, and then ends the iteration too.
     */
-    async fn run<'a, I>(&mut self, input: I) -> Result<(), Box<dyn Error + Send + Sync>>
-    where
-        I: IntoIterator<Item = &'a [u8]> + Send,
-        <I as IntoIterator>::IntoIter: Send,
-    {
-        self.run_with_channel(input).1
+    pub async fn next(&mut self) -> Option<Result<Vec<u8>, EngineError>> {
+        if self.done {
+            return None;
+        }
+        match self.engine.read_chunk_bytes().await {
+            Ok(chunk) => Some(Ok(chunk)),
+            Err(EngineError::Eof(partial)) => {
+                self.done = true;
+                (!partial.is_empty()).then_some(Ok(partial))
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
     }
 }
 
+/// The shared body of [`run_with_channel`](Engine::run_with_channel) and
+/// [`run_silent`](Engine::run_silent).
+fn exchange<'s, E: Engine, I>(
+    engine: &'s mut E,
+    input: I,
+    echo: bool,
+) -> (UnboundedReceiver<Event>, impl Future<Output = Result<(), EngineError>> + Send + use<'s, E, I>)
+where
+    I: IntoIterator + Send,
+    <I as IntoIterator>::IntoIter: Send,
+    <I as IntoIterator>::Item: AsRef<[u8]> + Send,
+{
+    let (sender, receiver) = unbounded_channel();
+    let future = async move {
+        let mut stdout = stdout();
+        for i in input {
+            let i = i.as_ref();
+            write(&engine.read_chunk_bytes().await?, E::LINE_SEP, &sender, echo).await?;
+            let (r1, r2) = join!(
+                async {
+                    engine.write_all(i).await?;
+                    engine.flush().await
+                },
+                async {
+                    if echo {
+                        stdout.write_all(i).await?;
+                        stdout.write_u8(b'\n').await?;
+                    }
+                    Ok::<(), IOError>(())
+                }
+            );
+            r1?;
+            r2?;
+            let _ = sender.send(Event::Sent(i.to_vec()));
+        }
+
+        write(&engine.drain_remaining().await, E::LINE_SEP, &sender, echo).await?;
+        Ok(())
+    };
+    (receiver, future)
+}
+
+/**
+Forwards `chunk` to `sender` as one [`Event::Recv`] per `separator`-terminated line, and echoes it
+to stdout if `echo` is set. The events carry the raw bytes; only the echo is decoded, lossily, so
+that binary output does not garble the terminal. Forwarding stops quietly once the receiver is
+gone. An empty separator cannot split anything, so the chunk is then sent whole.
+*/
 async fn write(
-    chunk: String,
-    sender: Option<&UnboundedSender<String>>,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    if let Some(sender) = sender {
-        if !sender.is_closed() {
-            for part in chunk.split("\n") {
-                sender.send(part.to_owned())?;
-            }
+    chunk: &[u8],
+    separator: &[u8],
+    sender: &UnboundedSender<Event>,
+    echo: bool,
+) -> Result<(), IOError> {
+    let mut rest = chunk;
+    while !rest.is_empty() {
+        let end = match separator.len() {
+            0 => None,
+            n => rest.windows(n).position(|w| w == separator).map(|i| i + n),
+        };
+        let (line, tail) = rest.split_at(end.unwrap_or(rest.len()));
+        if sender.send(Event::Recv(line.to_vec())).is_err() {
+            break;
         }
+        rest = tail;
     }
-    Ok(stdout().write_all(chunk.as_bytes()).await?)
+    if echo {
+        stdout().write_all(String::from_utf8_lossy(chunk).as_bytes()).await?;
+    }
+    Ok(())
 }
 
 /**
@@ -134,17 +1148,540 @@ use engine::{connect, ssh::{Session, KnownHosts}};
 let session = Session::connect_mux("remote.host.org", KnownHosts::Strict).await.unwrap();
 // Remove the comment on this line ...
 let mut handle = // connect!(@ssh session, "/path/to/executable").await.unwrap();
+    // connect!(@local "./executable", ["--flag"]).await.unwrap(); (... or this one ...)
     connect!(@tcp "www.example.com:65535").await.unwrap(); // ... and comment out this line ...
                                                            // to "switch modes".
 # }
 ```
+
+Targets don't have to be literals:
+```no_run
+# use engine::connect;
+# #[tokio::main]
+# async fn main() {
+let port = std::env::args().nth(1).unwrap();
+let mut handle = connect!(@tcp format!("www.example.com:{}", port)).await.unwrap();
+# }
+```
+
+`@ssh` borrows a session given by name. Any other expression is passed on as it is, the way
+[`SSH::new`](crate::SSH::new) takes it, so a shared `Arc<Session>` works too:
+```no_run
+# use engine::{connect, ssh::{KnownHosts, Session}};
+# use std::sync::Arc;
+# #[tokio::main]
+# async fn main() {
+let session = Session::connect_mux("remote.host.org", KnownHosts::Strict).await.unwrap();
+let borrowed = connect!(@ssh session, "/path/to/executable").await.unwrap();
+
+let shared = Arc::new(Session::connect_mux("remote.host.org", KnownHosts::Strict).await.unwrap());
+let handle = connect!(@ssh shared.clone(), "/path/to/executable").await.unwrap();
+tokio::spawn(async move { drop(handle) });
+# }
+```
 */
 #[macro_export]
 macro_rules! connect {
-    (@tcp $url: literal) => {{
-        engine::tcp($url)
+    (@tcp $url: expr) => {{
+        $crate::tcp($url)
+    }};
+    (@ssh $session: ident, $file: expr) => {{
+        $crate::SSH::new_leak(&$session, $file, $crate::ssh::Matcher::default())
+    }};
+    (@ssh $session: ident, $file: expr, $matcher: expr) => {{
+        $crate::SSH::new_leak(&$session, $file, $matcher)
+    }};
+    (@ssh $session: expr, $file: expr) => {{
+        $crate::SSH::new_leak($session, $file, $crate::ssh::Matcher::default())
+    }};
+    (@ssh $session: expr, $file: expr, $matcher: expr) => {{
+        $crate::SSH::new_leak($session, $file, $matcher)
+    }};
+    (@listen $addr: expr) => {{
+        $crate::listen($addr)
     }};
-    (@ssh $session: ident, $file: literal) => {{
-        engine::SSH::new_leak(&$session, $file)
+    (@unix $path: expr) => {{
+        $crate::unix($path)
     }};
+    (@local $path: expr, $args: expr) => {{
+        $crate::Process::new($path, $args)
+    }};
+    (@local $path: expr) => {{
+        $crate::Process::new($path, [] as [&str; 0])
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chunk, ChunkEnd, Engine, EngineError, Event};
+    use crate::wrap::Adapter;
+    use std::{
+        io::ErrorKind,
+        time::{Duration, Instant},
+    };
+    use tokio::{
+        io::{duplex, join, sink, AsyncReadExt, AsyncWriteExt, DuplexStream},
+        join,
+    };
+
+    impl Engine for DuplexStream {
+        const TIMEOUT: Duration = Duration::from_millis(10);
+    }
+
+    #[tokio::test]
+    async fn recv_until() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"banner\n> rest").await.unwrap();
+        assert_eq!(local.recv_until(b"> ").await.unwrap(), b"banner\n> ");
+    }
+
+    #[tokio::test]
+    async fn recv_until_eof() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"no prompt").await.unwrap();
+        drop(remote);
+
+        let error = local.recv_until(b"> ").await.unwrap_err();
+        assert!(matches!(error, EngineError::Eof(partial) if partial == b"no prompt"));
+    }
+
+    #[tokio::test]
+    async fn recv_before() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"KEY: value\nnext").await.unwrap();
+        local.recv_until(b": ").await.unwrap();
+        assert_eq!(local.recv_before(b"\n").await.unwrap(), b"value");
+        assert_eq!(local.recv_n(4).await.unwrap(), b"next");
+    }
+
+    #[tokio::test]
+    async fn recv_until_max() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"desynced output").await.unwrap();
+
+        let error = local.recv_until_max(b"> ", 8).await.unwrap_err();
+        assert!(matches!(error, EngineError::DelimiterNotFound(partial) if partial == b"desynced"));
+    }
+
+    #[tokio::test]
+    async fn recv_timeout() {
+        let (mut local, mut remote) = duplex(64);
+        assert_eq!(local.recv_timeout(Duration::from_millis(10)).await.unwrap(), None);
+
+        remote.write_all(b"done").await.unwrap();
+        let received = local.recv_timeout(Duration::from_millis(10)).await.unwrap();
+        assert_eq!(received.as_deref(), Some(b"done" as &[u8]));
+
+        drop(remote);
+        let error = local.recv_timeout(Duration::from_millis(10)).await.unwrap_err();
+        assert!(matches!(error, EngineError::Eof(partial) if partial.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn recv_line() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"Name: \nAge: \n").await.unwrap();
+        assert_eq!(local.recv_line().await.unwrap(), b"Name: ");
+        assert_eq!(local.recv_line_keep().await.unwrap(), b"Age: \n");
+    }
+
+    #[tokio::test]
+    async fn recv_until_any() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"Error: no such item\n> ").await.unwrap();
+
+        let (buf, index) = local.recv_until_any(&[b"> ", b"Error"]).await.unwrap();
+        assert_eq!((buf.as_slice(), index), (b"Error" as &[u8], 1));
+        assert_eq!(local.recv_until_any(&[b"> ", b"Error"]).await.unwrap().1, 0);
+    }
+
+    #[tokio::test]
+    async fn recv_match() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"noise flag{r3c0v3r3d} noise").await.unwrap();
+
+        let (buf, range) = local
+            .recv_match(|buf| {
+                let start = buf.windows(5).position(|w| w == b"flag{")? + 5;
+                let end = start + buf[start..].iter().position(|&b| b == b'}')?;
+                Some(start..end)
+            })
+            .await
+            .unwrap();
+        assert_eq!(buf, b"noise flag{r3c0v3r3d}");
+        assert_eq!(&buf[range], b"r3c0v3r3d");
+    }
+
+    #[cfg(feature = "regex")]
+    #[tokio::test]
+    async fn recv_regex() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"puts @ 0x7f12ab\nsystem @ 0x7f34cd\n").await.unwrap();
+
+        let re = regex::bytes::Regex::new(r"0x([0-9a-f]+)\n").unwrap();
+        assert_eq!(local.recv_regex(&re).await.unwrap(), b"puts @ 0x7f12ab\n");
+        assert_eq!(local.recv_regex_group(&re).await.unwrap(), b"7f34cd");
+
+        drop(remote);
+        let error = local.recv_regex(&re).await.unwrap_err();
+        assert!(matches!(error, EngineError::Eof(partial) if partial.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn recv_all() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"first\n").await.unwrap();
+        remote.write_all(b"second\n").await.unwrap();
+        drop(remote);
+        assert_eq!(local.recv_all().await.unwrap(), b"first\nsecond\n");
+    }
+
+    #[tokio::test]
+    async fn recv_n() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(&[0xef, 0xbe, 0xad, 0xde, 0xff]).await.unwrap();
+        assert_eq!(local.recv_n(4).await.unwrap(), [0xef, 0xbe, 0xad, 0xde]);
+
+        drop(remote);
+        let error = local.recv_n(4).await.unwrap_err();
+        assert!(matches!(error, EngineError::Eof(partial) if partial == [0xff]));
+    }
+
+    #[tokio::test]
+    async fn read_chunk_bytes() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(&[0x00, 0xff, 0x7f, 0x00]).await.unwrap();
+        assert_eq!(local.read_chunk_bytes().await.unwrap(), [0x00, 0xff, 0x7f, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn read_chunk_bytes_large() {
+        let (mut local, mut remote) = duplex(4096);
+        let dump: Vec<u8> = (0..100_000).map(|i| i as u8).collect();
+        let (_, chunk) = join!(remote.write_all(&dump), local.read_chunk_bytes());
+        assert_eq!(chunk.unwrap(), dump);
+    }
+
+    #[tokio::test]
+    async fn read_line_chunk() {
+        let (mut local, mut remote) = duplex(64);
+        let slow = async {
+            remote.write_all(b"Enter your na").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            remote.write_all(b"me:\n").await.unwrap();
+        };
+        let (_, chunk) = join!(slow, local.read_line_chunk());
+        assert_eq!(chunk.unwrap(), "Enter your name:\n");
+
+        remote.write_all(b"cut").await.unwrap();
+        drop(remote);
+        let error = local.read_line_chunk_bytes().await.unwrap_err();
+        assert!(matches!(error, EngineError::Eof(partial) if partial == b"cut"));
+    }
+
+    #[tokio::test]
+    async fn read_chunk_with_end() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"> ").await.unwrap();
+        let chunk = local.read_chunk_with_end().await;
+        assert_eq!(chunk, Chunk { data: b"> ".to_vec(), ended: ChunkEnd::Idle });
+
+        remote.write_all(b"Segmentation fault").await.unwrap();
+        drop(remote);
+        assert_eq!(local.read_chunk_with_end().await.ended, ChunkEnd::Eof);
+        assert_eq!(local.read_chunk_with_end().await, Chunk { data: vec![], ended: ChunkEnd::Eof });
+    }
+
+    #[tokio::test]
+    async fn read_error() {
+        let (_, broken) = duplex(64).0.split();
+        let stream = join(b"partial".chain(broken), sink());
+        let mut engine = Adapter::new(stream, Duration::from_millis(10));
+
+        let chunk = engine.read_last_chunk_with_end().await;
+        let ended = ChunkEnd::Error(ErrorKind::Unsupported);
+        assert_eq!(chunk, Chunk { data: b"partial".to_vec(), ended });
+        assert_eq!(engine.read_chunk_with_end().await, Chunk { data: vec![], ended });
+
+        let error = engine.read_chunk_bytes().await.unwrap_err();
+        assert!(matches!(error, EngineError::Io(e) if e.kind() == ErrorKind::Unsupported));
+    }
+
+    #[tokio::test]
+    async fn chunks() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"first").await.unwrap();
+        let mut chunks = local.chunks();
+        assert_eq!(chunks.next().await.unwrap().unwrap(), b"first");
+
+        remote.write_all(b"last").await.unwrap();
+        drop(remote);
+        assert_eq!(chunks.next().await.unwrap().unwrap(), b"last");
+        assert!(chunks.next().await.is_none());
+        assert!(chunks.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn read_chunk_closed() {
+        let (mut local, remote) = duplex(64);
+        drop(remote);
+        assert!(local.read_chunk().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn send_line() {
+        let (mut local, mut remote) = duplex(64);
+        local.send_line(b"1").await.unwrap();
+        assert_eq!(remote.recv_n(2).await.unwrap(), b"1\n");
+    }
+
+    #[tokio::test]
+    async fn send_int_hex() {
+        let (mut local, mut remote) = duplex(64);
+        local.send_int(-3).await.unwrap();
+        local.send_hex(b"\xde\xad").await.unwrap();
+        assert_eq!(remote.recv_n(8).await.unwrap(), b"-3\ndead\n");
+    }
+
+    #[tokio::test]
+    async fn remote_closed() {
+        let (mut local, remote) = duplex(64);
+        drop(remote);
+        let error = EngineError::from(local.send_line(b"AAAA").await.unwrap_err());
+        assert!(matches!(error, EngineError::RemoteClosed(_)));
+    }
+
+    #[tokio::test]
+    async fn send_slow() {
+        let (mut local, mut remote) = duplex(64);
+        let writer = local.send_slow_chunks(b"ABCDE", 2, Duration::from_millis(20));
+        let reader = async {
+            let mut buf = [0; 8];
+            let first = remote.read(&mut buf).await.unwrap();
+            assert_eq!(&buf[..first], b"AB");
+            remote.recv_n(3).await.unwrap()
+        };
+        let (written, rest) = join!(writer, reader);
+        written.unwrap();
+        assert_eq!(rest, b"CDE");
+    }
+
+    #[tokio::test]
+    async fn send_line_after() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"Name: ").await.unwrap();
+        assert_eq!(local.send_line_after(b": ", b"AAAA").await.unwrap(), b"Name: ");
+        assert_eq!(remote.recv_n(5).await.unwrap(), b"AAAA\n");
+    }
+
+    #[tokio::test]
+    async fn clean() {
+        let (mut local, mut remote) = duplex(64);
+        let start = Instant::now();
+        assert!(local.clean(Duration::from_secs(5)).await.is_empty());
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        remote.write_all(b"1) Buy\n2) Sell\n").await.unwrap();
+        assert_eq!(local.clean(Duration::from_millis(10)).await, b"1) Buy\n2) Sell\n");
+    }
+
+    #[tokio::test]
+    async fn recv_available() {
+        let (mut local, mut remote) = duplex(64);
+        assert!(local.recv_available().await.is_empty());
+
+        remote.write_all(b"leak: 0x7ffe").await.unwrap();
+        assert_eq!(local.recv_available().await, b"leak: 0x7ffe");
+        assert!(local.recv_available().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn forward_stdout() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"$ ").await.unwrap();
+        assert_eq!(local.forward_stdout().await.unwrap(), 2);
+
+        drop(remote);
+        assert_eq!(local.forward_stdout().await.unwrap(), 0);
+    }
+
+    #[test]
+    fn timing() {
+        let (local, _remote) = duplex(64);
+        assert_eq!(local.timing(), (Duration::from_millis(10), 1));
+    }
+
+    #[tokio::test]
+    async fn split() {
+        let (local, mut remote) = duplex(64);
+        let (mut reader, mut writer) = local.split();
+        assert_eq!(reader.timeout(), Duration::from_millis(10));
+
+        let reading = tokio::spawn(async move {
+            let line = reader.recv_line().await.unwrap();
+            (reader, line)
+        });
+        writer.send_line(b"ping").await.unwrap();
+        assert_eq!(remote.recv_line().await.unwrap(), b"ping");
+        remote.write_all(b"pong\n").await.unwrap();
+
+        let (mut reader, line) = reading.await.unwrap();
+        assert_eq!(line, b"pong");
+        assert!(reader.send_line(b"x").await.is_err());
+        assert!(writer.recv_n(1).await.is_err());
+        let mut local = reader.unsplit(writer);
+        remote.write_all(b"!").await.unwrap();
+        assert_eq!(local.recv_n(1).await.unwrap(), b"!");
+    }
+
+    #[tokio::test]
+    async fn read_chunk_timeout() {
+        let (mut local, mut remote) = duplex(64);
+        let slow = async {
+            remote.write_all(b"computing").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            remote.write_all(b"... done").await.unwrap();
+        };
+        let (_, chunk) = tokio::join!(slow, local.read_chunk_timeout(Duration::from_millis(100)));
+        assert_eq!(chunk.unwrap(), "computing... done");
+    }
+
+    #[tokio::test]
+    async fn read_chunk_rounds() {
+        let (mut local, mut remote) = duplex(64);
+        let slow = async {
+            remote.write_all(b"loading").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            remote.write_all(b"... ok").await.unwrap();
+        };
+        let (_, chunk) = tokio::join!(slow, local.read_chunk_rounds(9));
+        assert_eq!(chunk.unwrap(), "loading... ok");
+    }
+
+    #[tokio::test]
+    async fn read_chunk_stable() {
+        let (mut local, mut remote) = duplex(64);
+        let slow = async {
+            remote.write_all(b"a").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            remote.write_all(b"b").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(120)).await;
+            remote.write_all(b"c").await.unwrap();
+        };
+        let stable = async {
+            let chunk = local.read_chunk_stable(3, Duration::from_millis(20)).await.unwrap();
+            (chunk, local.recv_n(1).await.unwrap())
+        };
+        let (_, (chunk, rest)) = tokio::join!(slow, stable);
+        assert_eq!((chunk, rest), (b"ab".to_vec(), b"c".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn drain_remaining() {
+        let (mut local, mut remote) = duplex(64);
+        let slow = async {
+            remote.write_all(b"result: ").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(40)).await;
+            remote.write_all(b"42").await.unwrap();
+        };
+        let (_, tail) = tokio::join!(slow, local.drain_remaining());
+        assert_eq!(tail, b"result: 42");
+    }
+
+    #[tokio::test]
+    async fn replay() {
+        let path = std::env::temp_dir().join(format!("engine-replay-{}", std::process::id()));
+        std::fs::write(
+            &path,
+            "0.000100 < Welcome, PID 100\\nName: \n0.500000 > admin\\n\n0.500200 < Hi, admin\\n\n",
+        )
+        .unwrap();
+
+        let (mut local, mut remote) = duplex(64);
+        let target = async {
+            remote.write_all(b"Welcome, PID 4242\nName: ").await.unwrap();
+            assert_eq!(remote.recv_line().await.unwrap(), b"admin");
+            remote.write_all(b"Hi, admin\n").await.unwrap();
+        };
+        let (_, received) = join!(target, local.replay(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(received.unwrap(), b"Welcome, PID 4242\nName: Hi, admin\n");
+    }
+
+    #[tokio::test]
+    async fn run_with_channel() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"one\ntwo\nthr").await.unwrap();
+
+        let (mut receiver, future) = local.run_with_channel([] as [&[u8]; 0]);
+        future.await.unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = receiver.recv().await {
+            lines.push(line);
+        }
+        let recv = |line: &[u8]| Event::Recv(line.to_vec());
+        assert_eq!(lines, [recv(b"one\n"), recv(b"two\n"), recv(b"thr")]);
+    }
+
+    #[tokio::test]
+    async fn run_with_channel_events() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"Name: ").await.unwrap();
+
+        let (mut receiver, future) = local.run_silent([b"admin\n"]);
+        let target = async {
+            assert_eq!(remote.recv_line().await.unwrap(), b"admin");
+            remote.write_all(b"Hi\n").await.unwrap();
+        };
+        let (result, _) = join!(future, target);
+        result.unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = receiver.recv().await {
+            events.push(event);
+        }
+        assert_eq!(
+            events,
+            [
+                Event::Recv(b"Name: ".to_vec()),
+                Event::Sent(b"admin\n".to_vec()),
+                Event::Recv(b"Hi\n".to_vec())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn run_with_channel_dropped() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"nobody\nlistening").await.unwrap();
+
+        let (receiver, future) = local.run_silent(vec![b"unused".to_vec()]);
+        drop(receiver);
+        future.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn run_timeout() {
+        let (mut local, _remote) = duplex(64);
+        let result = local.run_timeout([b"never sent"], Duration::from_millis(50)).await;
+        assert!(matches!(result, Err(EngineError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn run_keeps_writing() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"> ").await.unwrap();
+
+        local.run([b"first".to_vec()]).await.unwrap();
+        local.send_line(b"more").await.unwrap();
+        assert_eq!(remote.recv_n(10).await.unwrap(), b"firstmore\n");
+    }
+
+    #[tokio::test]
+    async fn run_and_finish() {
+        let (mut local, mut remote) = duplex(64);
+        remote.write_all(b"> ").await.unwrap();
+
+        local.run_and_finish([b"last".to_vec()]).await.unwrap();
+        assert_eq!(remote.recv_all().await.unwrap(), b"last");
+    }
 }
@@ -9,16 +9,41 @@ An engine. You know, for doing things?
 See module-level documentation for more details.
 */
 
+mod mock;
+#[cfg(feature = "test-util")]
+pub use mock::MockEngine;
+
+mod process;
+#[cfg(feature = "process")]
+pub use process::Process;
+
+mod serial;
+#[cfg(all(feature = "serial", unix))]
+pub use serial::{serial, Serial};
+
 pub mod ssh;
 #[cfg(feature = "ssh")]
 pub use ssh::SSH;
 
 mod tcp;
 #[cfg(feature = "tcp")]
-pub use tcp::tcp;
+pub use tcp::{brute, listen, tcp, tcp_addr, tcp_nodelay, tcp_retry, tcp_timeout};
+
+mod tls;
+#[cfg(feature = "tls")]
+pub use tls::{tls, tls_insecure, TlsStream};
+
+mod udp;
+#[cfg(feature = "udp")]
+pub use udp::{udp, Udp};
+
+mod unix;
+#[cfg(all(feature = "unix", unix))]
+pub use unix::unix;
 
 mod common;
-#[cfg(any(feature = "ssh", feature = "tcp"))]
 pub use common::*;
 
 pub mod util;
+
+pub mod wrap;
@@ -0,0 +1,198 @@
+#![cfg(feature = "test-util")]
+
+use crate::Engine;
+use std::{
+    io::Result as IOResult,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/**
+An in-memory [`Engine`] that plays a scripted target, for testing exploit logic offline. The
+script is a list of rules, "once `expect` has been written, respond with `respond`", which fire in
+order; everything written is kept for assertions.
+
+Until a rule fires, reads wait like they would on a silent target, so the chunk readers end their
+chunks as usual. With [`eof_when_done`](MockEngine::eof_when_done), the target closes the stream
+once every rule has fired and all of its output has been read.
+
+```
+use engine::{Engine, MockEngine};
+
+# #[tokio::main]
+# async fn main() {
+let mut target = MockEngine::new()
+    .with_output(b"Name: ")
+    .on(b"\n", b"Hello!\n")
+    .eof_when_done();
+
+target.recv_until(b"Name: ").await.unwrap();
+target.send_line(b"admin").await.unwrap();
+assert_eq!(target.recv_all().await.unwrap(), b"Hello!\n");
+assert_eq!(target.written(), b"admin\n");
+# }
+```
+*/
+#[derive(Debug, Default)]
+pub struct MockEngine {
+    rules: Vec<(Vec<u8>, Vec<u8>)>,
+    fired: usize,
+    output: Vec<u8>,
+    written: Vec<u8>,
+    /// Where in `written` the next rule starts looking for its `expect`.
+    matched: usize,
+    eof: bool,
+    waker: Option<Waker>,
+}
+
+impl MockEngine {
+    /// An engine with no output and no rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `output` readable straight away, e.g. a banner or the first prompt.
+    pub fn with_output(mut self, output: &[u8]) -> Self {
+        self.output.extend_from_slice(output);
+        self
+    }
+
+    /**
+    Adds a rule: once `expect` has been written (after whatever the previous rule matched),
+    `respond` becomes readable.
+    */
+    pub fn on(mut self, expect: &[u8], respond: &[u8]) -> Self {
+        self.rules.push((expect.to_vec(), respond.to_vec()));
+        self
+    }
+
+    /// Closes the stream once every rule has fired and all of the output has been read.
+    pub fn eof_when_done(mut self) -> Self {
+        self.eof = true;
+        self
+    }
+
+    /// Everything written to the engine so far.
+    pub fn written(&self) -> &[u8] {
+        &self.written
+    }
+
+    /// How many rules have not fired yet.
+    pub fn remaining(&self) -> usize {
+        self.rules.len() - self.fired
+    }
+
+    /// Fires as many rules as what has been written allows.
+    fn fire(&mut self) {
+        while let Some((expect, respond)) = self.rules.get(self.fired) {
+            let unmatched = &self.written[self.matched..];
+            let end = if expect.is_empty() {
+                0
+            } else {
+                match unmatched.windows(expect.len()).position(|w| w == expect) {
+                    Some(i) => i + expect.len(),
+                    None => break,
+                }
+            };
+            self.matched += end;
+            self.output.extend_from_slice(respond);
+            self.fired += 1;
+        }
+    }
+}
+
+impl AsyncRead for MockEngine {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IOResult<()>> {
+        if self.output.is_empty() {
+            if self.eof && self.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            self.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let n = self.output.len().min(buf.remaining());
+        buf.put_slice(&self.output[..n]);
+        self.output.drain(..n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for MockEngine {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<IOResult<usize>> {
+        self.written.extend_from_slice(buf);
+        self.fire();
+        if !self.output.is_empty() || (self.eof && self.remaining() == 0) {
+            if let Some(waker) = self.waker.take() {
+                waker.wake();
+            }
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IOResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IOResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Engine for MockEngine {
+    const TIMEOUT: Duration = Duration::from_millis(10);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockEngine;
+    use crate::{Engine, Event};
+
+    #[tokio::test]
+    async fn scripted() {
+        let mut target = MockEngine::new()
+            .with_output(b"> ")
+            .on(b"1\n", b"Size: ")
+            .on(b"\n", b"ok\n> ")
+            .eof_when_done();
+
+        assert_eq!(target.read_chunk().await.unwrap(), "> ");
+        target.send_line(b"1").await.unwrap();
+        assert_eq!(target.recv_until(b": ").await.unwrap(), b"Size: ");
+        assert_eq!(target.remaining(), 1);
+        target.send_int(32).await.unwrap();
+        assert_eq!(target.recv_all().await.unwrap(), b"ok\n> ");
+        assert_eq!(target.written(), b"1\n32\n");
+    }
+
+    #[tokio::test]
+    async fn binary_run() {
+        let mut target =
+            MockEngine::new().with_output(b"\xff\x00> ").on(b"go", b"\x90\xff\n").eof_when_done();
+
+        let (mut events, run) = target.run_silent([b"go"]);
+        run.await.unwrap();
+        assert_eq!(events.recv().await, Some(Event::Recv(b"\xff\x00> ".to_vec())));
+        assert_eq!(events.recv().await, Some(Event::Sent(b"go".to_vec())));
+        assert_eq!(events.recv().await, Some(Event::Recv(b"\x90\xff\n".to_vec())));
+    }
+
+    #[tokio::test]
+    async fn silent_until_matched() {
+        let mut target = MockEngine::new().on(b"ping", b"pong");
+        assert!(target.read_last_chunk_bytes().await.is_empty());
+        target.send_line(b"pi").await.unwrap();
+        assert!(target.read_last_chunk_bytes().await.is_empty());
+        target.send_line(b"ping").await.unwrap();
+        assert_eq!(target.read_chunk_bytes().await.unwrap(), b"pong");
+    }
+}
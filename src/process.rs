@@ -0,0 +1,84 @@
+#![cfg(feature = "process")]
+
+use crate::Engine;
+use std::{
+    ffi::OsStr,
+    io::{Error as IOError, Result as IOResult},
+    pin::Pin,
+    process::Stdio,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    process::{Child, Command},
+};
+
+/// A local process.
+pub struct Process(pub Child);
+
+impl Process {
+    /**
+    Launches the executable at `path` with `args`. Returns an [`Engine`] connected to that process.
+    The process is killed when the [`Process`] is dropped.
+    */
+    pub async fn new<I, S>(path: impl AsRef<OsStr>, args: I) -> IOResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        Ok(Self(
+            Command::new(path)
+                .args(args)
+                .stdout(Stdio::piped())
+                .stdin(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()?,
+        ))
+    }
+}
+
+impl AsyncWrite for Process {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, IOError>> {
+        Pin::new(self.0.stdin.as_mut().unwrap()).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), IOError>> {
+        Pin::new(self.0.stdin.as_mut().unwrap()).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), IOError>> {
+        Pin::new(self.0.stdin.as_mut().unwrap()).poll_shutdown(cx)
+    }
+}
+
+impl AsyncRead for Process {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IOResult<()>> {
+        Pin::new(self.0.stdout.as_mut().unwrap()).poll_read(cx, buf)
+    }
+}
+
+impl Engine for Process {
+    const TIMEOUT: Duration = Duration::from_millis(50);
+    const REPEAT: usize = 2;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Engine;
+
+    #[tokio::test]
+    async fn cat() {
+        let mut cat = crate::connect!(@local "cat").await.unwrap();
+        cat.send_line(b"meow").await.unwrap();
+        assert_eq!(cat.recv_until(b"\n").await.unwrap(), b"meow\n");
+    }
+}
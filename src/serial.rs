@@ -0,0 +1,161 @@
+#![cfg(all(feature = "serial", unix))]
+
+use crate::Engine;
+use std::{
+    fs::{File, OpenOptions},
+    io::{Error as IOError, ErrorKind, Read, Result as IOResult, Write},
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    path::Path,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::io::{unix::AsyncFd, AsyncRead, AsyncWrite, ReadBuf};
+
+/// A serial port (e.g. `/dev/ttyUSB0`), configured for raw 8N1 I/O.
+pub struct Serial(AsyncFd<File>);
+
+impl Serial {
+    /// Opens the serial device at `path` and sets it to `baud`.
+    pub fn open(path: impl AsRef<Path>, baud: u32) -> IOResult<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+            .open(path)?;
+        configure(&file, speed(baud)?)?;
+        Ok(Self(AsyncFd::new(file)?))
+    }
+
+    /// The underlying device file.
+    pub fn file(&self) -> &File {
+        self.0.get_ref()
+    }
+}
+
+/// Puts the terminal behind `file` in raw mode at `speed`.
+fn configure(file: &File, speed: libc::speed_t) -> IOResult<()> {
+    let fd = file.as_raw_fd();
+    // SAFETY: `termios` is plain data, and `fd` stays open for the duration of these calls.
+    unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut termios) != 0 {
+            return Err(IOError::last_os_error());
+        }
+        libc::cfmakeraw(&mut termios);
+        termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+        if libc::cfsetspeed(&mut termios, speed) != 0
+            || libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0
+        {
+            return Err(IOError::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn speed(baud: u32) -> IOResult<libc::speed_t> {
+    Ok(match baud {
+        1200 => libc::B1200,
+        2400 => libc::B2400,
+        4800 => libc::B4800,
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        #[cfg(target_os = "linux")]
+        460800 => libc::B460800,
+        #[cfg(target_os = "linux")]
+        921600 => libc::B921600,
+        _ => {
+            return Err(IOError::new(
+                ErrorKind::InvalidInput,
+                format!("unsupported baud rate {}", baud),
+            ))
+        }
+    })
+}
+
+impl AsyncRead for Serial {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IOResult<()>> {
+        loop {
+            let mut guard = ready!(self.0.poll_read_ready(cx))?;
+            match guard.try_io(|fd| fd.get_ref().read(buf.initialize_unfilled())) {
+                Ok(result) => {
+                    buf.advance(result?);
+                    return Poll::Ready(Ok(()));
+                }
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for Serial {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<IOResult<usize>> {
+        loop {
+            let mut guard = ready!(self.0.poll_write_ready(cx))?;
+            match guard.try_io(|fd| fd.get_ref().write(buf)) {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IOResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IOResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Engine for Serial {
+    const TIMEOUT: Duration = Duration::from_millis(200);
+    const REPEAT: usize = 2;
+}
+
+/// Shorthand for [`Serial::open`].
+pub fn serial(path: impl AsRef<Path>, baud: u32) -> IOResult<Serial> {
+    Serial::open(path, baud)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::serial;
+    use crate::Engine;
+    use std::{
+        ffi::CStr,
+        fs::File,
+        io::{Read, Write},
+        os::unix::io::FromRawFd,
+    };
+
+    #[tokio::test]
+    async fn pty() {
+        // SAFETY: standard pseudo-terminal setup; the master fd is handed to `File` exactly once.
+        let (mut console, path) = unsafe {
+            let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            assert!(master >= 0 && libc::grantpt(master) == 0 && libc::unlockpt(master) == 0);
+            let mut name = [0; 64];
+            assert_eq!(libc::ptsname_r(master, name.as_mut_ptr(), name.len()), 0);
+            let path = CStr::from_ptr(name.as_ptr()).to_str().unwrap().to_owned();
+            (File::from_raw_fd(master), path)
+        };
+        let mut board = serial(&path, 115200).unwrap();
+
+        console.write_all(b"U-Boot> ").unwrap();
+        assert_eq!(board.recv_until(b"> ").await.unwrap(), b"U-Boot> ");
+
+        board.send_line(b"help").await.unwrap();
+        let mut buf = [0; 5];
+        console.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"help\n");
+    }
+}
@@ -1,97 +1,530 @@
 #![cfg(feature = "ssh")]
 
-use crate::Engine;
+use crate::{Engine, EngineError, Event};
+use futures_util::future::join_all;
 use openssh::{Child, Error as SSHError, Stdio};
 pub use openssh::{KnownHosts, Session};
+use shell_escape::escape;
 use std::{
-    io::{Error as IOError, Result as IOResult},
-    path::PathBuf,
+    fmt::{self, Debug, Formatter},
+    io::{Error as IOError, ErrorKind, Result as IOResult},
+    ops::Deref,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
     pin::Pin,
+    process::ExitStatus,
     str::FromStr,
     time::Duration,
 };
-use tokio::io::{stdin, AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader};
+use tokio::{
+    fs::File,
+    io::{
+        copy, stdin, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+    },
+    time::timeout,
+};
+
+/**
+A process launched over an SSH session. `S` is how the [`Session`] is held: a `&Session` borrows
+it, while an `Arc<Session>` shares ownership of it, so that the engine is `'static` and can be
+stored or moved into a spawned task.
+
+```no_run
+use engine::{ssh::{KnownHosts, Session}, Engine, SSH};
+use std::sync::Arc;
+
+# #[tokio::main]
+# async fn main() {
+let session = Arc::new(Session::connect_mux("user@example.com", KnownHosts::Strict).await.unwrap());
+let mut handle = SSH::new(session.clone(), "/challenge/vuln").await.unwrap();
+tokio::spawn(async move { handle.send_line(b"hello").await.unwrap() });
+# }
+```
 
-/// An SSH session.
-pub struct SSH<'a>(pub Child<&'a Session>, String);
+Dropping an `SSH` closes the process's stdin and the local end of the channel, but does not kill
+the remote process: it keeps running until it exits by itself, e.g. on reading EOF. Call
+[`kill`](SSH::kill) first if it must not outlive the engine.
+*/
+pub struct SSH<S>(Option<Child<S>>, String, Matcher, u32);
 
-impl<'a> SSH<'a> {
+/// How [`SSH::pids`] picks out the remote process with `pgrep`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Matcher {
+    /// Match the executable's file name (`pgrep NAME`).
+    #[default]
+    Name,
+    /// Like [`Name`](Matcher::Name), but only the most recently started match (`pgrep -n NAME`).
+    Newest,
+    /// Match `pattern` against the full command line (`pgrep -f PATTERN`).
+    Full(String),
+}
+
+impl<S> SSH<S> {
+    /**
+    The remote process. Its [`session`](Child::session) can run auxiliary commands on the same
+    host, e.g. `cat /proc/PID/maps`.
+    */
+    pub fn child(&self) -> &Child<S> {
+        self.0.as_ref().unwrap()
+    }
+
+    /// The remote process, mutably.
+    pub fn child_mut(&mut self) -> &mut Child<S> {
+        self.0.as_mut().unwrap()
+    }
+}
+
+impl<S: Deref<Target = Session> + Clone + Send + Unpin> SSH<S> {
     /**
     Launches an executable `file` using a remote [`session`](Session). Returns an [`Engine`]
     connected to that process.
     */
-    pub async fn new(session: &'a Session, file: &str) -> Result<Self, SSHError> {
+    pub async fn new(session: S, file: &str) -> Result<Self, SSHError> {
+        Self::new_with(session, file, &[], &[]).await
+    }
+
+    /**
+    Like [`new`](Self::new), but passes `args` to the executable and sets the environment
+    variables in `env` for it. The path, arguments and values are shell-escaped. Variable names
+    must match `[A-Za-z_][A-Za-z0-9_]*`; any other name is rejected with an
+    [`InvalidInput`](ErrorKind::InvalidInput) error (as [`ChildIo`](SSHError::ChildIo)) before
+    anything is run.
+    */
+    pub async fn new_with(
+        session: S,
+        file: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+    ) -> Result<Self, SSHError> {
+        Self::spawn(session, file, args, env, false).await
+    }
+
+    /**
+    Like [`new`](Self::new), but also captures the process's stderr, which can then be read with
+    [`read_stderr`](Self::read_stderr).
+    */
+    pub async fn new_stderr(session: S, file: &str) -> Result<Self, SSHError> {
+        Self::spawn(session, file, &[], &[], true).await
+    }
+
+    /**
+    Copies the local file at `local_path` to `remote_path` on the remote host, e.g. a payload or a
+    helper binary to run with [`new`](Self::new) afterwards. If the local file is executable, the
+    remote copy is made executable too. Returns `remote_path`.
+
+    The file is streamed through `cat` on the remote host, so only `sh` and `cat` need to exist
+    there. Failing to read the local file is reported as [`ChildIo`](SSHError::ChildIo), and a
+    failing remote command (e.g. an unwritable target directory) as [`Remote`](SSHError::Remote).
+
+    ```no_run
+    use engine::{ssh::{KnownHosts, Session}, SSH};
+
+    # #[tokio::main]
+    # async fn main() {
+    let session = Session::connect_mux("user@example.com", KnownHosts::Strict).await.unwrap();
+    let helper = SSH::upload(&session, "./helper", "/tmp/helper").await.unwrap();
+    let mut handle = SSH::new(&session, &helper).await.unwrap();
+    # }
+    ```
+    */
+    pub async fn upload(
+        session: S,
+        local_path: impl AsRef<Path>,
+        remote_path: &str,
+    ) -> Result<String, SSHError> {
+        let mut file = File::open(&local_path).await.map_err(SSHError::ChildIo)?;
+        let mode = file.metadata().await.map_err(SSHError::ChildIo)?.permissions().mode();
+
+        let target = escape(remote_path.into());
+        let mut command = format!("cat > {}", target);
+        if mode & 0o111 != 0 {
+            command += &format!(" && chmod +x {}", target);
+        }
+        let mut child = Session::to_command(session, "sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .spawn()
+            .await?;
+
+        let mut stdin = child.stdin().take().unwrap();
+        let sent = async {
+            copy(&mut file, &mut stdin).await?;
+            stdin.shutdown().await
+        }
+        .await;
+        drop(stdin);
+
+        // A failing `cat` also breaks the pipe, so its status says more than the write error.
+        let status = child.wait().await?;
+        if !status.success() {
+            return Err(SSHError::Remote(IOError::other(format!(
+                "uploading to {} failed with {}",
+                remote_path, status
+            ))));
+        }
+        sent.map_err(SSHError::ChildIo)?;
+        Ok(remote_path.to_owned())
+    }
+
+    async fn spawn(
+        session: S,
+        file: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        stderr: bool,
+    ) -> Result<Self, SSHError> {
+        let mut child = Session::to_command(session, "sh")
+            .arg("-c")
+            .arg(command_line(file, args, env)?)
+            .stdout(Stdio::piped())
+            .stdin(Stdio::piped())
+            .stderr(if stderr { Stdio::piped() } else { Stdio::inherit() })
+            .spawn()
+            .await?;
+        let pid = read_pid(child.stdout().as_mut().unwrap()).await?;
+
         Ok(Self(
-            session.shell(file).stdout(Stdio::piped()).stdin(Stdio::piped()).spawn().await?,
+            Some(child),
             PathBuf::from_str(file).unwrap().file_name().unwrap().to_str().unwrap().to_owned(),
+            Matcher::default(),
+            pid,
         ))
     }
 
+    /// Sets the [`Matcher`] used by [`pids`](Self::pids) and everything built on it.
+    pub fn with_matcher(mut self, matcher: Matcher) -> Self {
+        self.2 = matcher;
+        self
+    }
+
+    /// The file name of the executable, as used by [`Matcher::Name`].
+    pub fn name(&self) -> &str {
+        &self.1
+    }
+
+    /**
+    Reads whatever the process has written to stderr, stopping once nothing new arrives for
+    [`timeout`](Engine::timeout). Fails if stderr was not captured (see
+    [`new_stderr`](Self::new_stderr)).
+    */
+    pub async fn read_stderr(&mut self) -> IOResult<Vec<u8>> {
+        let idle = Engine::timeout(self);
+        let stderr = self.child_mut().stderr().as_mut().ok_or_else(|| {
+            IOError::new(ErrorKind::NotConnected, "stderr was not captured; see SSH::new_stderr")
+        })?;
+
+        let (mut r, mut buf) = (Vec::new(), [0; 1024]);
+        loop {
+            match timeout(idle, stderr.read(&mut buf)).await {
+                Ok(Ok(0)) | Err(_) => return Ok(r),
+                Ok(Ok(n)) => r.extend_from_slice(&buf[..n]),
+                Ok(Err(e)) => return Err(e),
+            }
+        }
+    }
+
     /**
     Like [`new`](Self::new), but pauses the process as soon as it launches. Then, uses `pgrep`
     on the remote host to find the process's PID and reports it back to you, and then waits for you
-    to press ENTER.
+    to press ENTER. `matcher` decides which process `pgrep` reports (see [`pids`](Self::pids));
+    when several match, the last one is reported.
     */
-    pub async fn new_leak(session: &'a Session, file: &str) -> Result<Self, SSHError> {
-        let r = Self::new(session, file).await?;
-        r.leak_pid().await;
+    pub async fn new_leak(session: S, file: &str, matcher: Matcher) -> Result<Self, SSHError> {
+        let r = Self::new(session, file).await?.with_matcher(matcher);
+        if let Some(pid) = r.pids().await.pop() {
+            println!("PID is {}. Waiting . . .", pid);
+            println!("[Press ENTER to continue]");
+
+            BufReader::new(stdin()).read_line(&mut String::new()).await.unwrap();
+        }
         Ok(r)
     }
 
-    /// See [`new_leak`](Self::new_leak).
-    async fn leak_pid(&self) {
-        let grepout = String::from_utf8(
-            self.0.session().command("pgrep").arg(&self.1).output().await.unwrap().stdout,
-        )
-        .unwrap();
-        if let Some(v) = {
-            let mut ids = grepout.split(|b| b == '\n').collect::<Vec<_>>();
-            ids.pop();
-            ids.pop()
-        } {
-            println!("PID is {}. Waiting . . .", v);
-            println!("[Press ENTER to continue]");
+    /**
+    The remote PID of this process, as reported by the shell that launched it. The shell `exec`s
+    the executable, so this is the executable's own PID rather than a guess like [`pids`](Self::pids).
+    */
+    pub fn pid(&self) -> u32 {
+        self.3
+    }
 
-            BufReader::new(stdin()).read_line(&mut String::new()).await.unwrap();
+    /**
+    Every remote PID matching this process's [`Matcher`], in the order `pgrep` reports them.
+    Empty if `pgrep` fails or finds nothing. Unlike [`pid`](Self::pid), this may include unrelated
+    processes that happen to match, or children the process has forked.
+    */
+    pub async fn pids(&self) -> Vec<u32> {
+        let mut command = Session::to_command(self.child().session(), "pgrep");
+        match &self.2 {
+            Matcher::Name => command.arg(&self.1),
+            Matcher::Newest => command.arg("-n").arg(&self.1),
+            Matcher::Full(pattern) => command.arg("-f").arg(pattern),
+        };
+
+        match command.output().await {
+            Ok(output) => String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|l| l.trim().parse().ok())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /**
+    Closes the process's stdin and sends `SIGKILL` to its [`pid`](Self::pid) on the remote host.
+    Only that process is signalled, not any children it has forked. Dropping an [`SSH`] only tears
+    down the local end of the channel, so call this when the remote process must not outlive it.
+    */
+    pub async fn kill(&mut self) -> Result<(), SSHError> {
+        self.child_mut().stdin().take();
+        Session::to_command(self.child().session(), "kill")
+            .arg("-KILL")
+            .arg(self.3.to_string())
+            .status()
+            .await?;
+        Ok(())
+    }
+
+    /**
+    Closes stdin, waits for the remote process to exit and returns its status. A process killed
+    by a signal (e.g. `SIGSEGV`) reports it through
+    [`ExitStatusExt::signal`](std::os::unix::process::ExitStatusExt::signal).
+    */
+    pub async fn wait(mut self) -> Result<ExitStatus, SSHError> {
+        self.0.take().unwrap().wait().await
+    }
+
+    /**
+    Runs `gdbserver --attach :PORT PID` on the remote host against this process and prints the
+    command for connecting to it. The returned [`Child`] is the `gdbserver` process; keep it alive
+    for as long as the debugger is needed.
+    */
+    pub async fn gdb_attach(&self, gdbserver_port: u16) -> Result<Child<S>, SSHError> {
+        let pid = self.pid();
+        let server = Session::to_command(self.child().session(), "gdbserver")
+            .arg("--attach")
+            .arg(format!(":{}", gdbserver_port))
+            .arg(pid.to_string())
+            .spawn()
+            .await?;
+
+        println!("gdbserver attached to PID {} on port {}.", pid, gdbserver_port);
+        println!("Connect with: gdb -ex 'target remote <host>:{}'", gdbserver_port);
+        Ok(server)
+    }
+}
+
+/**
+The `sh -c` script behind [`SSH::spawn`]: prints the shell's PID, then `exec`s `file` with `args`
+and `env`. Everything but the variable names is shell-escaped, and those are checked instead.
+*/
+fn command_line(file: &str, args: &[&str], env: &[(&str, &str)]) -> Result<String, SSHError> {
+    let mut command = String::from("echo $$; ");
+    for (key, value) in env {
+        if !is_env_name(key) {
+            return Err(SSHError::ChildIo(IOError::new(
+                ErrorKind::InvalidInput,
+                format!("invalid environment variable name {:?}", key),
+            )));
+        }
+        command += &format!("{}={} ", key, escape((*value).into()));
+    }
+    command += &format!("exec {}", escape(file.into()));
+    for arg in args {
+        command += &format!(" {}", escape((*arg).into()));
+    }
+    Ok(command)
+}
+
+/// Reads the PID that the `echo $$` in [`command_line`] prints before the executable starts.
+async fn read_pid(stdout: &mut (impl AsyncRead + Unpin)) -> Result<u32, SSHError> {
+    let mut line = Vec::new();
+    loop {
+        match stdout.read_u8().await.map_err(SSHError::ChildIo)? {
+            b'\n' => break,
+            byte => line.push(byte),
+        }
+    }
+    std::str::from_utf8(&line).ok().and_then(|line| line.parse().ok()).ok_or_else(|| {
+        SSHError::Remote(IOError::new(ErrorKind::InvalidData, "the remote shell reported no PID"))
+    })
+}
+
+/// Whether `name` can be assigned in a `sh` command prefix, i.e. matches `[A-Za-z_][A-Za-z0-9_]*`.
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/**
+Several processes launched over the same [`Session`], e.g. to race them against each other.
+`openssh` multiplexes all of them over the session's one connection.
+
+```no_run
+use engine::ssh::{KnownHosts, SSHGroup, Session};
+
+# #[tokio::main]
+# async fn main() {
+let session = Session::connect_mux("user@example.com", KnownHosts::Strict).await.unwrap();
+let mut group = SSHGroup::spawn(&session, &["/challenge/vuln"; 2]).await.unwrap();
+let results = group.run_all([vec!["1", "withdraw 100"], vec!["1", "withdraw 100"]]).await;
+println!("{:?}", results);
+# }
+```
+*/
+pub struct SSHGroup<S>(pub Vec<SSH<S>>);
+
+impl<S: Deref<Target = Session> + Clone + Send + Unpin> SSHGroup<S> {
+    /// Launches each of `files` over `session` (see [`SSH::new`]), in order.
+    pub async fn spawn(session: S, files: &[&str]) -> Result<Self, SSHError> {
+        let mut engines = Vec::with_capacity(files.len());
+        for file in files {
+            engines.push(SSH::new(session.clone(), file).await?);
         }
+        Ok(Self(engines))
+    }
+
+    /**
+    Drives every process with its own input concurrently, pairing `inputs` with the processes in
+    order, and waits for all of them. Each run is [silent](Engine::run_silent); what each process
+    sent is returned instead, as raw bytes and in the same order. Processes without an input are
+    not run.
+    */
+    pub async fn run_all<I>(
+        &mut self,
+        inputs: impl IntoIterator<Item = I>,
+    ) -> Vec<Result<Vec<u8>, EngineError>>
+    where
+        I: IntoIterator + Send,
+        <I as IntoIterator>::IntoIter: Send,
+        <I as IntoIterator>::Item: AsRef<[u8]> + Send,
+    {
+        let (mut receivers, runs): (Vec<_>, Vec<_>) =
+            self.0.iter_mut().zip(inputs).map(|(engine, input)| engine.run_silent(input)).unzip();
+
+        join_all(runs)
+            .await
+            .into_iter()
+            .zip(receivers.iter_mut())
+            .map(|(result, receiver)| {
+                result?;
+                let mut transcript = Vec::new();
+                while let Ok(event) = receiver.try_recv() {
+                    if let Event::Recv(line) = event {
+                        transcript.extend(line);
+                    }
+                }
+                Ok(transcript)
+            })
+            .collect()
     }
 }
 
-impl AsyncWrite for SSH<'_> {
+impl<S: Unpin> AsyncWrite for SSH<S> {
     fn poll_write(
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
         buf: &[u8],
     ) -> std::task::Poll<Result<usize, IOError>> {
-        Pin::new(self.0.stdin().as_mut().unwrap()).poll_write(cx, buf)
+        Pin::new(self.child_mut().stdin().as_mut().unwrap()).poll_write(cx, buf)
     }
 
     fn poll_flush(
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Result<(), IOError>> {
-        Pin::new(self.0.stdin().as_mut().unwrap()).poll_flush(cx)
+        Pin::new(self.child_mut().stdin().as_mut().unwrap()).poll_flush(cx)
     }
 
     fn poll_shutdown(
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Result<(), IOError>> {
-        Pin::new(self.0.stdin().as_mut().unwrap()).poll_shutdown(cx)
+        Pin::new(self.child_mut().stdin().as_mut().unwrap()).poll_shutdown(cx)
     }
 }
 
-impl AsyncRead for SSH<'_> {
+impl<S: Unpin> AsyncRead for SSH<S> {
     fn poll_read(
         mut self: Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> std::task::Poll<IOResult<()>> {
-        Pin::new(self.0.stdout().as_mut().unwrap()).poll_read(cx, buf)
+        Pin::new(self.child_mut().stdout().as_mut().unwrap()).poll_read(cx, buf)
     }
 }
 
-impl Engine for SSH<'_> {
+impl<S: Send + Unpin> Engine for SSH<S> {
     const TIMEOUT: Duration = Duration::from_millis(50);
     const REPEAT: usize = 3;
 }
+
+impl<S> Drop for SSH<S> {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.0 {
+            child.stdin().take();
+        }
+    }
+}
+
+impl<S: Send + Unpin> Debug for SSH<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (timeout, repeat) = self.timing();
+        f.debug_struct("SSH")
+            .field("name", &self.1)
+            .field("pid", &self.3)
+            .field("matcher", &self.2)
+            .field("timeout", &timeout)
+            .field("repeat", &repeat)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{command_line, is_env_name, read_pid};
+    use std::{fs, os::unix::fs::PermissionsExt, process::Command};
+
+    #[test]
+    fn escaped_command_line() {
+        let dir = std::env::temp_dir().join(format!("engine-ssh-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("odd name; $(id) 'q\".sh");
+        fs::write(&file, "#!/bin/sh\nprintf '%s|' \"$0\" \"$@\"\necho \"$GREETING\"\n").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o755)).unwrap();
+        let file = file.to_str().unwrap();
+
+        let command = command_line(file, &["a b", "$HOME"], &[("GREETING", "hi; there")]).unwrap();
+        let output = Command::new("sh").arg("-c").arg(command).output().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let (pid, rest) = stdout.split_once('\n').unwrap();
+        assert!(pid.parse::<u32>().is_ok());
+        assert_eq!(rest, format!("{}|a b|$HOME|hi; there\n", file));
+
+        assert!(command_line(file, &[], &[("A;id", "")]).is_err());
+    }
+
+    #[tokio::test]
+    async fn pid_line() {
+        let mut stdout: &[u8] = b"4242\nhello\n";
+        assert_eq!(read_pid(&mut stdout).await.unwrap(), 4242);
+        assert_eq!(stdout, b"hello\n");
+
+        assert!(read_pid(&mut &b"sh: 1: exec: nope: not found\n"[..]).await.is_err());
+        assert!(read_pid(&mut &b"4242"[..]).await.is_err());
+    }
+
+    #[test]
+    fn env_names() {
+        for name in ["PATH", "_", "LD_PRELOAD", "a1"] {
+            assert!(is_env_name(name), "{}", name);
+        }
+        for name in ["", "1A", "A B", "A=B", "A;id", "$(id)", "É"] {
+            assert!(!is_env_name(name), "{}", name);
+        }
+    }
+}
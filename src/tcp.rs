@@ -1,15 +1,229 @@
 #![cfg(feature = "tcp")]
 
 use crate::Engine;
-use std::time::Duration;
-use tokio::net::TcpStream;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use std::{future::Future, net::SocketAddr, time::Duration};
+use tokio::{
+    net::{lookup_host, TcpListener, TcpStream, ToSocketAddrs},
+    time::{sleep, timeout},
+};
 
 impl Engine for TcpStream {
     const TIMEOUT: Duration = Duration::from_millis(50);
     const REPEAT: usize = 5;
 }
 
-/// Shorthand to open a TCP connection [using tokio](tokio::net::TcpStream).
-pub async fn tcp(url: &'static str) -> std::io::Result<TcpStream> {
-    Ok(TcpStream::connect(url).await?)
+/**
+Shorthand to open a TCP connection [using tokio](tokio::net::TcpStream). `addr` can be anything
+tokio can resolve, such as a `"host:port"` string built at runtime or a `(host, port)` tuple.
+IPv6 literals go in brackets (`"[::1]:1337"`); literal addresses are parsed without a DNS lookup.
+*/
+pub async fn tcp(addr: impl ToSocketAddrs) -> std::io::Result<TcpStream> {
+    tcp_retry(addr, 1, Duration::ZERO).await
+}
+
+/**
+Like [`tcp`], but connects to an already resolved `addr`, IPv4 or IPv6, without going through
+name resolution at all.
+
+```no_run
+use engine::tcp_addr;
+use std::net::{Ipv6Addr, SocketAddr};
+
+# #[tokio::main]
+# async fn main() {
+let handle = tcp_addr(SocketAddr::from((Ipv6Addr::LOCALHOST, 1337))).await.unwrap();
+# }
+```
+*/
+pub async fn tcp_addr(addr: SocketAddr) -> std::io::Result<TcpStream> {
+    TcpStream::connect(addr).await
+}
+
+/**
+Like [`tcp`], but sets `TCP_NODELAY` on the connection, so that small writes go out at once
+instead of being held back by Nagle's algorithm. Use this when the timing of a request/response
+exchange matters.
+*/
+pub async fn tcp_nodelay(addr: impl ToSocketAddrs) -> std::io::Result<TcpStream> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+/**
+Like [`tcp`], but makes up to `attempts` connection attempts. Between attempts it waits, starting at
+`backoff` and doubling the delay every time. `addr` is resolved once, before the first attempt.
+Returns the last error if every attempt fails, and an
+[`InvalidInput`](std::io::ErrorKind::InvalidInput) error if `attempts` is 0.
+*/
+pub async fn tcp_retry(
+    addr: impl ToSocketAddrs,
+    attempts: usize,
+    backoff: Duration,
+) -> std::io::Result<TcpStream> {
+    if attempts == 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "tcp_retry needs at least one attempt",
+        ));
+    }
+    let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
+
+    let mut delay = backoff;
+    for _ in 1..attempts {
+        match TcpStream::connect(&addrs[..]).await {
+            Ok(stream) => return Ok(stream),
+            Err(_) => {
+                sleep(delay).await;
+                delay = delay.saturating_mul(2);
+            }
+        }
+    }
+    TcpStream::connect(&addrs[..]).await
+}
+
+/**
+Like [`tcp`], but gives up with a [`TimedOut`](std::io::ErrorKind::TimedOut) error if the
+connection is not established within `dur`.
+*/
+pub async fn tcp_timeout(addr: impl ToSocketAddrs, dur: Duration) -> std::io::Result<TcpStream> {
+    timeout(dur, TcpStream::connect(addr))
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "connection timed out"))?
+}
+
+/**
+Binds a TCP listener on `addr`, accepts one incoming connection and returns it. Use this to catch
+connections that the target makes back to you, such as reverse shells.
+*/
+pub async fn listen(addr: impl ToSocketAddrs) -> std::io::Result<TcpStream> {
+    Ok(TcpListener::bind(addr).await?.accept().await?.0)
+}
+
+/**
+Tries `candidates` against `addr` over up to `concurrency` connections at once: each candidate gets
+a fresh connection, which is handed to `f` along with the candidate. Returns the first candidate
+for which `f` returns `true` (which is not necessarily the first one in order), or [`None`] if none
+did. A candidate whose connection fails counts as a miss, so a flaky target does not end the run;
+if no candidate succeeds and any connection failed, the last connection error is returned instead
+of [`None`].
+
+```no_run
+use engine::{brute, Engine};
+
+# #[tokio::main]
+# async fn main() {
+let canary_byte = brute("127.0.0.1:1337", 16, 0..=255u8, |mut handle, byte| async move {
+    let mut payload = vec![b'A'; 40];
+    payload.push(byte);
+    handle.send_line_after(b"> ", &payload).await.is_ok()
+        && !handle.recv_all().await.unwrap_or_default().ends_with(b"stack smashing detected\n")
+})
+.await
+.unwrap();
+# }
+```
+*/
+pub async fn brute<A, I, F, Fut>(
+    addr: A,
+    concurrency: usize,
+    candidates: I,
+    f: F,
+) -> std::io::Result<Option<I::Item>>
+where
+    A: ToSocketAddrs + Clone,
+    I: IntoIterator,
+    I::Item: Clone,
+    F: Fn(TcpStream, I::Item) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let (mut candidates, f) = (candidates.into_iter(), &f);
+    let (mut running, mut error) = (FuturesUnordered::new(), None);
+    loop {
+        while running.len() < concurrency.max(1) {
+            let Some(candidate) = candidates.next() else { break };
+            let addr = addr.clone();
+            running.push(async move {
+                let stream = TcpStream::connect(addr).await?;
+                Ok::<_, std::io::Error>((f(stream, candidate.clone()).await, candidate))
+            });
+        }
+
+        match running.next().await {
+            None => return error.map_or(Ok(None), Err),
+            Some(Ok((true, candidate))) => return Ok(Some(candidate)),
+            Some(Ok((false, _))) => {}
+            Some(Err(e)) => error = Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{brute, tcp_retry};
+    use crate::Engine;
+    use std::{io::ErrorKind, time::Duration};
+    use tokio::{
+        io::AsyncWriteExt,
+        net::{TcpListener, TcpStream},
+    };
+
+    #[tokio::test]
+    async fn retried() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert!(tcp_retry(addr, 3, Duration::from_millis(1)).await.is_ok());
+
+        drop(listener);
+        assert!(tcp_retry(addr, 3, Duration::from_millis(1)).await.is_err());
+
+        let error = tcp_retry(addr, 0, Duration::ZERO).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    /// Answers every connection's one-byte guess with whether it was 0x42.
+    fn serve(listener: TcpListener) {
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let guess = stream.recv_n(1).await.unwrap()[0];
+                    let reply: &[u8] = if guess == 0x42 { b"yes" } else { b"no" };
+                    stream.write_all(reply).await.unwrap();
+                });
+            }
+        });
+    }
+
+    async fn guess(mut handle: TcpStream, byte: u8) -> bool {
+        handle.write_all(&[byte]).await.unwrap();
+        handle.recv_all().await.unwrap() == b"yes"
+    }
+
+    #[tokio::test]
+    async fn brute_forced() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        serve(listener);
+
+        assert_eq!(brute(addr, 8, 0..=255u8, guess).await.unwrap(), Some(0x42));
+        assert_eq!(brute(addr, 4, [1u8, 2, 3], guess).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn brute_flaky() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let error = brute(addr, 4, [1u8, 2, 3], guess).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ConnectionRefused);
+
+        // The target only comes up after the first few candidates failed to connect.
+        let candidates = (0..=255u8).inspect(move |&byte| {
+            if byte == 5 {
+                let listener = std::net::TcpListener::bind(addr).unwrap();
+                listener.set_nonblocking(true).unwrap();
+                serve(TcpListener::from_std(listener).unwrap());
+            }
+        });
+        assert_eq!(brute(addr, 1, candidates, guess).await.unwrap(), Some(0x42));
+    }
 }
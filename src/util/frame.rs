@@ -0,0 +1,74 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+/// A byte order, for helpers that are not split into `_be` variants.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+/**
+The CRC-32 (IEEE 802.3, as used by zlib, PNG and Ethernet) of `data`.
+
+```
+use engine::util::crc32;
+
+assert_eq!(crc32(b"123456789"), 0xcbf43926);
+```
+*/
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        (0..8)
+            .fold(crc ^ *byte as u32, |crc, _| (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg()))
+    })
+}
+
+/**
+Prepends the length of `payload` to it, as a `width`-byte integer in `endian` byte order. Fails if
+the length does not fit in `width` bytes.
+
+```
+use engine::{flat, util::{crc32, frame_with_len, pack::p32_be, Endian}};
+
+let message = b"HELLO";
+let framed = frame_with_len(message, 2, Endian::Big).unwrap();
+assert_eq!(framed, b"\x00\x05HELLO");
+
+// Length prefix, then the payload with its checksum.
+let packet = frame_with_len(&flat!(message, p32_be(crc32(message))), 4, Endian::Little).unwrap();
+assert_eq!(&packet[..4], [9, 0, 0, 0]);
+```
+*/
+pub fn frame_with_len(payload: &[u8], width: usize, endian: Endian) -> Result<Vec<u8>, FrameError> {
+    let length = payload.len();
+    if width < 8 && length as u64 >> (8 * width) != 0 {
+        return Err(FrameError { length, width });
+    }
+
+    let mut prefix = (length as u64).to_le_bytes().to_vec();
+    prefix.resize(width, 0);
+    if endian == Endian::Big {
+        prefix.reverse();
+    }
+    prefix.extend_from_slice(payload);
+    Ok(prefix)
+}
+
+/// Returned by [`frame_with_len`] when the length of the payload does not fit in the prefix.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FrameError {
+    /// The length of the payload.
+    pub length: usize,
+    /// The width of the length prefix, in bytes.
+    pub width: usize,
+}
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "a length of {} does not fit in {} bytes", self.length, self.width)
+    }
+}
+impl Error for FrameError {}
@@ -1,12 +1,48 @@
-use std::{fmt::LowerHex, num::ParseIntError, string::FromUtf8Error};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter, LowerHex},
+    num::ParseIntError,
+    string::FromUtf8Error,
+};
 
+pub mod b64;
+
+pub mod elf;
+
+mod de_bruijn;
+pub use de_bruijn::*;
+
+mod fmtstr;
+pub use fmtstr::*;
+
+mod frame;
+pub use frame::*;
+
+pub mod pack;
 pub mod pad;
+pub mod rop;
+pub mod shellcode;
 
 #[derive(Debug)]
 pub enum HexToBytesError {
     ParseError(ParseIntError),
     UTF8Error(FromUtf8Error),
+    /// The input had an odd number of hex digits.
+    OddLength(usize),
+    /// The input had a character that is not a hex digit, such as a sign.
+    InvalidDigit(char),
 }
+impl Display for HexToBytesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParseError(error) => write!(f, "invalid hex digit: {}", error),
+            Self::UTF8Error(error) => write!(f, "invalid UTF-8: {}", error),
+            Self::OddLength(length) => write!(f, "odd number of hex digits ({})", length),
+            Self::InvalidDigit(c) => write!(f, "invalid hex digit {:?}", c),
+        }
+    }
+}
+impl Error for HexToBytesError {}
 impl From<ParseIntError> for HexToBytesError {
     fn from(error: ParseIntError) -> Self {
         Self::ParseError(error)
@@ -24,10 +60,20 @@ input.
 */
 #[trait_variant::make(Send)]
 pub trait HexToBytes: LowerHex {
-    async fn hex_to_bytes(&self) -> Vec<u8>;
+    async fn hex_to_bytes(&self) -> Result<Vec<u8>, HexToBytesError>;
+
+    /// Like [`hex_to_bytes`](HexToBytes::hex_to_bytes), but least-significant byte first.
+    async fn hex_to_bytes_le(&self) -> Result<Vec<u8>, HexToBytesError>;
+
+    /**
+    Like [`hex_to_bytes`](HexToBytes::hex_to_bytes), but always returns `N` bytes: leading zero
+    bytes are kept (see [`pad_left`](pad::Left::pad_left)), and if the value needs more than `N`
+    bytes, only the lowest `N` are kept.
+    */
+    async fn hex_to_bytes_width<const N: usize>(&self) -> Result<[u8; N], HexToBytesError>;
 }
 impl<T: ?Sized + Send + Sync + LowerHex> HexToBytes for T {
-    async fn hex_to_bytes(&self) -> Vec<u8> {
+    async fn hex_to_bytes(&self) -> Result<Vec<u8>, HexToBytesError> {
         let s = {
             let mut r = format!("{:x}", self);
             if r.len() % 2 == 1 {
@@ -36,17 +82,142 @@ impl<T: ?Sized + Send + Sync + LowerHex> HexToBytes for T {
             r
         };
 
-        let mut r = Vec::new();
-        for chunk in s.as_bytes().chunks(2) {
-            r.push(u8::from_str_radix(&String::from_utf8(chunk.to_vec()).unwrap(), 16).unwrap())
+        s.as_bytes().chunks(2).map(hex_pair).collect()
+    }
+
+    async fn hex_to_bytes_le(&self) -> Result<Vec<u8>, HexToBytesError> {
+        let mut r = self.hex_to_bytes().await?;
+        r.reverse();
+        Ok(r)
+    }
+
+    async fn hex_to_bytes_width<const N: usize>(&self) -> Result<[u8; N], HexToBytesError> {
+        Ok(pad::Left::pad_left::<N>(self.hex_to_bytes().await?).await)
+    }
+}
+
+/**
+Parses a string of hex digits (e.g. `"41424344"`) into bytes. An optional `0x` or `0X` prefix is
+allowed, and whitespace anywhere in the string is ignored; any other character that is not a hex
+digit, including a sign, is an [`InvalidDigit`](HexToBytesError::InvalidDigit) error.
+*/
+pub fn hex_decode(s: &str) -> Result<Vec<u8>, HexToBytesError> {
+    let s = s.trim();
+    let s = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    let digits: Vec<u8> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if digits.len() % 2 == 1 {
+        return Err(HexToBytesError::OddLength(digits.len()));
+    }
+
+    digits.chunks(2).map(hex_pair).collect()
+}
+
+/// Parses two hex digits into the byte they spell.
+fn hex_pair(pair: &[u8]) -> Result<u8, HexToBytesError> {
+    let pair = String::from_utf8(pair.to_vec())?;
+    pair.chars().try_fold(0, |byte, c| match c.to_digit(16) {
+        Some(digit) => Ok(byte << 4 | digit as u8),
+        None => Err(HexToBytesError::InvalidDigit(c)),
+    })
+}
+
+/**
+Renders bytes as a lowercase hex string, two digits per byte. The inverse of [`HexToBytes`].
+*/
+#[trait_variant::make(Send)]
+pub trait BytesToHex: AsRef<[u8]> + Sync {
+    async fn to_hex(&self) -> String {
+        async { self.as_ref().iter().map(|b| format!("{:02x}", b)).collect() }
+    }
+
+    /// Like [`to_hex`](BytesToHex::to_hex), but puts `separator` between each pair of digits.
+    async fn to_hex_with(&self, separator: char) -> String {
+        async move {
+            self.as_ref()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(separator.encode_utf8(&mut [0; 4]))
         }
-        r
     }
 }
+impl BytesToHex for [u8] {}
+impl BytesToHex for Vec<u8> {}
+
+/**
+XORs `a` and `b` byte-wise. The shorter input is repeated to cover the longer one, so the result is
+as long as the longer input. If either input is empty, the other is returned unchanged.
+*/
+pub fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    if short.is_empty() {
+        return long.to_vec();
+    }
+    long.iter().zip(short.iter().cycle()).map(|(x, y)| x ^ y).collect()
+}
+
+/// XORs every byte of `data` with `key`.
+pub fn xor_key(data: &[u8], key: u8) -> Vec<u8> {
+    xor(data, &[key])
+}
+
+/**
+Rounds `value` up to the next multiple of `align`. `align` does not have to be a power of two.
+
+Panics if `align` is zero.
+*/
+pub fn align_up(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+/**
+Rounds `value` down to the previous multiple of `align`. `align` does not have to be a power of
+two.
+
+Panics if `align` is zero.
+*/
+pub fn align_down(value: usize, align: usize) -> usize {
+    value - value % align
+}
+
+/// Pads `payload` with `fill` up to a multiple of `align` bytes. Panics if `align` is zero.
+pub fn align_payload(payload: &mut Vec<u8>, align: usize, fill: u8) {
+    payload.resize(align_up(payload.len(), align), fill);
+}
+
+/// Concatenates `parts` into one buffer, in order. See also [`flat!`](crate::flat).
+pub fn flat(parts: &[&[u8]]) -> Vec<u8> {
+    parts.concat()
+}
+
+/**
+Concatenates payload pieces into one `Vec<u8>`. Each piece may be anything that is
+[`AsRef<[u8]>`](AsRef): byte strings, slices, arrays, `Vec<u8>`s, and so also packed integers.
+
+```
+use engine::{flat, util::pack::p64};
+
+let padding = [b'A'; 40];
+let payload = flat!(padding, p64(0x401136), b"/bin/sh\0");
+assert_eq!(payload.len(), 40 + 8 + 8);
+```
+*/
+#[macro_export]
+macro_rules! flat {
+    ($($part: expr),* $(,)?) => {{
+        $crate::util::flat(&[$(::core::convert::AsRef::<[u8]>::as_ref(&$part)),*])
+    }};
+}
 
 #[cfg(test)]
 mod tests {
-    use super::{pad::*, HexToBytes};
+    use super::{
+        align_down, align_payload, align_up, crc32, cyclic, cyclic_find, cyclic_find_n, cyclic_n,
+        cyclic_with, flat, fmtstr_payload, frame_with_len, hex_decode, pack::*, pad::*,
+        try_cyclic_find, try_cyclic_find_with, xor, xor_key, BytesToHex, CyclicError, Endian,
+        FrameError, HexToBytes, HexToBytesError, WriteSize,
+    };
+    use std::collections::HashMap;
 
     #[tokio::test]
     async fn left_padded() {
@@ -55,19 +226,325 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[tokio::test]
+    async fn left_padded_many() {
+        assert_eq!(vec![1, 2, 3].pad_left::<5>().await, [0, 0, 1, 2, 3]);
+        assert_eq!([1, 2, 3].pad_left::<2>().await, [2, 3]);
+    }
+
+    /// `pad_left` used to write the bytes it kept back to front, which one-byte inputs cannot show.
+    #[tokio::test]
+    async fn left_padded_order() {
+        let address = [0x00, 0x40, 0x11, 0x36];
+        assert_eq!(address.pad_left::<6>().await, [0, 0, 0x00, 0x40, 0x11, 0x36]);
+        assert_eq!(address.pad_left::<3>().await, [0x40, 0x11, 0x36]);
+        assert_eq!(address.pad_left_with::<5>(0xff).await, [0xff, 0x00, 0x40, 0x11, 0x36]);
+        assert_eq!(address.try_pad_left::<4>().await, Ok(address));
+        assert_eq!(address.pad_left_to(6).await, [0, 0, 0x00, 0x40, 0x11, 0x36]);
+    }
+
+    #[tokio::test]
+    async fn padded_with() {
+        assert_eq!(b"\xcc".pad_left_with::<4>(0x90).await, [0x90, 0x90, 0x90, 0xcc]);
+        assert_eq!(vec![b'B'].pad_right_with::<3>(b'A').await, *b"BAA");
+    }
+
+    #[tokio::test]
+    async fn padded_borrowed() {
+        let leak = vec![0x90, 0x23, 0xa5];
+        assert_eq!(leak[..2].pad_left::<4>().await, [0, 0, 0x90, 0x23]);
+        assert_eq!((&leak).pad_right::<4>().await, [0x90, 0x23, 0xa5, 0]);
+        assert_eq!(leak.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn try_padded() {
+        assert_eq!([1, 2].try_pad_right::<4>().await, Ok([1, 2, 0, 0]));
+        assert_eq!(vec![1; 9].try_pad_left::<8>().await, Err(PadError { length: 9, limit: 8 }));
+    }
+
+    #[tokio::test]
+    async fn padded_to() {
+        let len = 2 + 3;
+        assert_eq!([1, 2, 3].pad_left_to(len).await, vec![0, 0, 1, 2, 3]);
+        assert_eq!(vec![1, 2, 3].pad_left_to(2).await, vec![2, 3]);
+        assert_eq!(b"AB".pad_right_to(len).await, b"AB\0\0\0".to_vec());
+        assert_eq!([1, 2, 3][..].pad_right_to(1).await, vec![1]);
+    }
+
     #[tokio::test]
     async fn hexbytes() {
-        assert_eq!(
-            0x10203040u32.hex_to_bytes().await,
-            &[0x10u8, 0x20u8, 0x30u8, 0x40u8]
-        );
+        assert_eq!(0x10203040u32.hex_to_bytes().await.unwrap(), &[0x10u8, 0x20u8, 0x30u8, 0x40u8]);
+    }
+
+    #[tokio::test]
+    async fn hexbytes_le() {
+        assert_eq!(0x12030.hex_to_bytes_le().await.unwrap(), [0x30, 0x20, 0x01]);
+    }
+
+    #[tokio::test]
+    async fn hexbytes_width() {
+        assert_eq!(0x0000deadu32.hex_to_bytes_width::<4>().await.unwrap(), [0, 0, 0xde, 0xad]);
+        assert_eq!(0x1337beefu32.hex_to_bytes_width::<2>().await.unwrap(), [0xbe, 0xef]);
     }
 
     #[tokio::test]
     async fn right_padded_hexbytes() {
         assert_eq!(
-            &(0x12030.hex_to_bytes().await.pad_right::<4>().await) as &[u8],
+            &(0x12030.hex_to_bytes().await.unwrap().pad_right::<4>().await) as &[u8],
             &[0x01, 0x20, 0x30, 0]
         )
     }
+
+    #[test]
+    fn packed() {
+        assert_eq!(p8(0x41), [0x41]);
+        assert_eq!(p16(0x1337), [0x37, 0x13]);
+        assert_eq!(p64(0x7ffff7a52390), [0x90, 0x23, 0xa5, 0xf7, 0xff, 0x7f, 0, 0]);
+        assert_eq!(p32_be(0x10203040), [0x10, 0x20, 0x30, 0x40]);
+    }
+
+    #[test]
+    fn unpacked() {
+        assert_eq!(u16(&[0x37, 0x13]), 0x1337);
+        assert_eq!(u64(&[0x90, 0x23, 0xa5, 0xf7, 0xff, 0x7f]), 0x7ffff7a52390);
+        assert_eq!(u32_be(&[0xbe, 0xef]), 0xbeef);
+        assert_eq!(u64_be(&p64_be(0x401136)), 0x401136);
+        assert_eq!(u16(&[0x37, 0x13, 0xff]), 0x1337);
+        assert_eq!(u16_be(&[0xff, 0x13, 0x37]), 0x1337);
+        assert_eq!((u8(&[]), u8_be(&[])), (0, 0));
+    }
+
+    #[test]
+    fn framed() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"The quick brown fox jumps over the lazy dog"), 0x414fa339);
+
+        assert_eq!(frame_with_len(b"AB", 4, Endian::Little).unwrap(), b"\x02\0\0\0AB");
+        assert_eq!(frame_with_len(b"AB", 3, Endian::Big).unwrap(), b"\0\0\x02AB");
+        assert_eq!(frame_with_len(&[0; 255], 1, Endian::Big).unwrap()[0], 255);
+        assert_eq!(
+            frame_with_len(&[0; 256], 1, Endian::Big),
+            Err(FrameError { length: 256, width: 1 })
+        );
+    }
+
+    #[tokio::test]
+    async fn hexstring() {
+        assert_eq!(vec![0xde, 0xad, 0xbe, 0xef].to_hex().await, "deadbeef");
+        assert_eq!(b"\x00\n"[..].to_hex_with(':').await, "00:0a");
+    }
+
+    #[test]
+    fn hex_decoded() {
+        assert_eq!(hex_decode("41424344").unwrap(), b"ABCD");
+        assert_eq!(hex_decode(" 0xde ad\nbe ef ").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert!(matches!(hex_decode("abc"), Err(HexToBytesError::OddLength(3))));
+        assert_eq!(hex_decode("0XDEAD").unwrap(), [0xde, 0xad]);
+        assert!(matches!(hex_decode("zz"), Err(HexToBytesError::InvalidDigit('z'))));
+        assert!(matches!(hex_decode("+a"), Err(HexToBytesError::InvalidDigit('+'))));
+        assert!(matches!(hex_decode("a+"), Err(HexToBytesError::InvalidDigit('+'))));
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn base64() {
+        use super::b64::{decode, decode_with, encode, encode_with, Alphabet, DecodeError};
+
+        for (plain, encoded) in
+            [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg==")]
+        {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+        }
+        assert_eq!(encode_with(&[0xfb, 0xff, 0xbf], Alphabet::UrlSafe), "-_-_");
+        assert_eq!(decode_with("-_8", Alphabet::UrlSafe).unwrap(), [0xfb, 0xff]);
+        assert_eq!(decode("Zm9v!"), Err(DecodeError::InvalidByte(4, b'!')));
+        assert_eq!(decode("Zm9vY"), Err(DecodeError::InvalidLength(5)));
+    }
+
+    #[cfg(feature = "shellcode")]
+    #[test]
+    fn shellcode() {
+        use super::shellcode::{EXECVE_BIN_SH_I386, EXECVE_BIN_SH_X86_64};
+
+        for payload in [EXECVE_BIN_SH_X86_64, EXECVE_BIN_SH_I386] {
+            assert!(!payload.contains(&0));
+        }
+        assert_eq!((EXECVE_BIN_SH_X86_64.len(), EXECVE_BIN_SH_I386.len()), (27, 24));
+    }
+
+    #[cfg(all(feature = "elf", target_os = "linux"))]
+    #[test]
+    fn elf() {
+        use super::elf::{Elf, ElfError};
+
+        let this = Elf::open(std::env::current_exe().unwrap()).unwrap();
+        assert!(this.symbol("main").is_some());
+        assert!(matches!(Elf::parse(b"MZ\x90\x00"), Err(ElfError::NotElf)));
+        assert!(matches!(Elf::parse(b"\x7fELF\x02\x01\x01"), Err(ElfError::Truncated)));
+    }
+
+    #[cfg(all(feature = "elf", target_os = "linux"))]
+    #[test]
+    fn libc_identified() {
+        use super::elf::{libc_id, Elf};
+
+        let dir = std::env::temp_dir().join(format!("engine-libc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(std::env::current_exe().unwrap(), dir.join("libc.so.6")).unwrap();
+        std::fs::write(dir.join("README"), "not a library").unwrap();
+        let this = Elf::open(dir.join("libc.so.6")).unwrap();
+        let base = 0x7f0000000000;
+        let main = base + this.symbol("main").unwrap();
+
+        let found = libc_id(&dir, &[("main", main)]).unwrap();
+        let missed = libc_id(&dir, &[("main", main + 8)]).unwrap();
+        let unknown = libc_id(&dir, &[("main", main), ("no_such_function", main)]).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            (found[0].path.file_name().unwrap(), found[0].base),
+            ("libc.so.6".as_ref(), base)
+        );
+        assert!(missed.is_empty() && unknown.is_empty());
+    }
+
+    #[cfg(all(feature = "elf", target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn rop() {
+        use super::{
+            elf::Elf,
+            rop::{Rop, RopError},
+        };
+
+        let this = Elf::open(std::env::current_exe().unwrap()).unwrap();
+        let (main, ret) = (this.symbol("main").unwrap(), this.search(b"\xc3").unwrap());
+        let mut rop = Rop::new(&this).with_base(0x1000);
+        rop.ret().unwrap().call("main", &[]).unwrap().raw(0x41);
+        assert_eq!(rop.build(), flat!(p64(0x1000 + ret), p64(0x1000 + main), p64(0x41)));
+
+        assert_eq!(
+            rop.call("no_such_function", &[]).err(),
+            Some(RopError::Symbol("no_such_function".into()))
+        );
+        assert_eq!(rop.call("main", &[0; 7]).err(), Some(RopError::TooManyArguments(7)));
+        if let Some(pop_rdi) = this.search(b"\x5f\xc3") {
+            let chain = Rop::new(&this).call("main", &[0xbeef]).unwrap().build();
+            assert_eq!(chain, flat!(p64(pop_rdi), p64(0xbeef), p64(main)));
+        }
+    }
+
+    #[test]
+    fn xored() {
+        assert_eq!(xor(b"\x01\x02\x03", b"\xff"), [0xfe, 0xfd, 0xfc]);
+        assert_eq!(xor(b"ab", b"\x01\x02\x03\x04\x05"), xor(b"\x01\x02\x03\x04\x05", b"ab"));
+        assert_eq!(xor(b"", b"kept"), b"kept");
+        assert_eq!(xor_key(&xor_key(b"secret", 0x42), 0x42), b"secret");
+    }
+
+    #[test]
+    fn cyclic_pattern() {
+        assert_eq!(cyclic(20), b"aaaabaaacaaadaaaeaaa");
+        assert_eq!(cyclic_with(12, b"AB", 3), b"AAABABBB");
+    }
+
+    #[test]
+    fn cyclic_found() {
+        let pattern = cyclic(256);
+        assert_eq!(cyclic_find(&pattern[137..141]), Some(137));
+        let register = u32::from_le_bytes(pattern[80..84].try_into().unwrap());
+        assert_eq!(cyclic_find(register), Some(80));
+        assert_eq!(cyclic_find(b"AAAA"), None);
+
+        let pattern = cyclic_n(1024, 8);
+        assert_eq!(cyclic_find_n(&pattern[600..608], 8, 1024), Some(600));
+        assert_eq!(cyclic_find_n(&pattern[600..608], 8, 608), Some(600));
+        assert_eq!(cyclic_find_n(&pattern[600..608], 8, 607), None);
+        assert_eq!(cyclic_find_n(b"AAAAAAAA", 8, 1024), None);
+        assert_eq!(cyclic_find_n(b"zzzzzzzz", 8, 1024), None);
+    }
+
+    #[test]
+    fn cyclic_contaminated() {
+        let mut crash = cyclic(64)[40..44].to_vec();
+        assert_eq!(try_cyclic_find(&crash), Ok(40));
+        crash[3] = 0x0a;
+        assert_eq!(try_cyclic_find(&crash), Err(CyclicError::NotInAlphabet(0x0a)));
+        assert_eq!(try_cyclic_find(b""), Err(CyclicError::Empty));
+        assert_eq!(try_cyclic_find_with(b"BA", b"AB", 2, usize::MAX), Err(CyclicError::NotFound));
+    }
+
+    #[test]
+    fn aligned() {
+        assert_eq!((align_up(0x1001, 0x1000), align_down(0x1fff, 0x1000)), (0x2000, 0x1000));
+        assert_eq!((align_up(10, 3), align_down(10, 3), align_up(9, 3)), (12, 9, 9));
+
+        let mut payload = b"/bin/sh\0AB".to_vec();
+        align_payload(&mut payload, 8, b'A');
+        assert_eq!(payload, b"/bin/sh\0ABAAAAAA");
+    }
+
+    #[test]
+    fn flattened() {
+        assert_eq!(flat(&[b"ab", &[], b"c"]), b"abc");
+        assert_eq!(crate::flat!(b"AA", vec![0x90], p16(0x4142), "!"), b"AA\x90BA!");
+    }
+
+    /// Runs the `%c` and `%N$hn`/`%N$hhn` conversions of `payload` the way `printf` would, with
+    /// the payload itself starting at argument `offset`, and returns the bytes written. Stops at
+    /// the first null or non-ASCII byte, which is where the addresses start.
+    fn run_fmtstr(payload: &[u8], offset: usize, width: usize) -> HashMap<u64, u8> {
+        let end = payload.iter().position(|b| *b == 0 || !b.is_ascii()).unwrap_or(payload.len());
+        let format = std::str::from_utf8(&payload[..end]).unwrap();
+        let (mut printed, mut memory, mut rest) = (0u64, HashMap::new(), format);
+        while let Some(start) = rest.find('%') {
+            printed += start as u64;
+            rest = &rest[start + 1..];
+            let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap();
+            let number: usize = rest[..digits].parse().unwrap();
+            rest = &rest[digits..];
+            if let Some(tail) = rest.strip_prefix('c') {
+                printed += number as u64;
+                rest = tail;
+                continue;
+            }
+            let bytes = if let Some(tail) = rest.strip_prefix("$hhn") {
+                rest = tail;
+                1
+            } else {
+                rest = rest.strip_prefix("$hn").unwrap();
+                2
+            };
+            let word = (number - offset) * width;
+            let address =
+                payload[word..word + width].iter().rev().fold(0, |a, b| a << 8 | *b as u64);
+            for i in 0..bytes {
+                memory.insert(address + i, (printed >> (8 * i)) as u8);
+            }
+        }
+        memory
+    }
+
+    #[test]
+    fn fmtstr_writes() {
+        let writes = vec![(0x404018, 0x7ffff7a52390), (0x404020, 0x41)];
+        for size in [WriteSize::Byte, WriteSize::Short] {
+            let payload = fmtstr_payload(writes.clone(), 6, 8, size);
+            assert_eq!(payload.len() % 8, 0);
+            let memory = run_fmtstr(&payload, 6, 8);
+            for (address, value) in &writes {
+                for (i, byte) in value.to_le_bytes().iter().enumerate() {
+                    assert_eq!(memory[&(address + i as u64)], *byte);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fmtstr_writes_32() {
+        let payload = fmtstr_payload(vec![(0x804a00c, 0xdeadbeef)], 4, 4, WriteSize::Byte);
+        let memory = run_fmtstr(&payload, 4, 4);
+        assert_eq!([0, 1, 2, 3].map(|i| memory[&(0x804a00c + i)]), [0xef, 0xbe, 0xad, 0xde]);
+    }
 }
@@ -0,0 +1,148 @@
+/*!
+Packs integers into bytes and unpacks them again, little-endian by default. The `_be` variants are
+big-endian.
+
+```
+use engine::util::pack::{p32, p64_be, u64};
+
+assert_eq!(p32(0xdeadbeef), [0xef, 0xbe, 0xad, 0xde]);
+assert_eq!(p64_be(0x401136), [0, 0, 0, 0, 0, 0x40, 0x11, 0x36]);
+// A 6-byte pointer leak.
+assert_eq!(u64(&[0x90, 0x23, 0xa5, 0xf7, 0xff, 0x7f]), 0x7ffff7a52390);
+```
+*/
+
+/// Packs a [`u8`](prim@u8).
+pub fn p8(value: u8) -> Vec<u8> {
+    vec![value]
+}
+
+/// Packs a [`u16`](prim@u16), little-endian.
+pub fn p16(value: u16) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+/// Packs a [`u32`](prim@u32), little-endian.
+pub fn p32(value: u32) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+/// Packs a [`u64`](prim@u64), little-endian.
+pub fn p64(value: u64) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+/// Packs a [`u8`](prim@u8). Identical to [`p8`]; provided for symmetry.
+pub fn p8_be(value: u8) -> Vec<u8> {
+    vec![value]
+}
+
+/// Packs a [`u16`](prim@u16), big-endian.
+pub fn p16_be(value: u16) -> Vec<u8> {
+    value.to_be_bytes().to_vec()
+}
+
+/// Packs a [`u32`](prim@u32), big-endian.
+pub fn p32_be(value: u32) -> Vec<u8> {
+    value.to_be_bytes().to_vec()
+}
+
+/// Packs a [`u64`](prim@u64), big-endian.
+pub fn p64_be(value: u64) -> Vec<u8> {
+    value.to_be_bytes().to_vec()
+}
+
+/**
+Unpacks a [`u8`](prim@u8) from the first byte of `data`, or `0` if `data` is empty.
+
+The unpacking functions zero-pad inputs that are too short on their most significant side, like
+[`pad_right`](super::pad::Right::pad_right) and [`pad_left`](super::pad::Left::pad_left) would,
+and drop the excess from that side if they are too long.
+*/
+pub fn u8(data: &[u8]) -> u8 {
+    u8::from_le_bytes(low_first(data))
+}
+
+/// Unpacks a little-endian [`u16`](prim@u16). See [`u8`](u8()) for short or long `data`.
+pub fn u16(data: &[u8]) -> u16 {
+    u16::from_le_bytes(low_first(data))
+}
+
+/// Unpacks a little-endian [`u32`](prim@u32). See [`u8`](u8()) for short or long `data`.
+pub fn u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes(low_first(data))
+}
+
+/// Unpacks a little-endian [`u64`](prim@u64). See [`u8`](u8()) for short or long `data`.
+pub fn u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(low_first(data))
+}
+
+/// Unpacks a [`u8`](prim@u8) from the last byte of `data`, or `0` if `data` is empty.
+pub fn u8_be(data: &[u8]) -> u8 {
+    u8::from_be_bytes(high_first(data))
+}
+
+/// Unpacks a big-endian [`u16`](prim@u16). See [`u8`](u8()) for short or long `data`.
+pub fn u16_be(data: &[u8]) -> u16 {
+    u16::from_be_bytes(high_first(data))
+}
+
+/// Unpacks a big-endian [`u32`](prim@u32). See [`u8`](u8()) for short or long `data`.
+pub fn u32_be(data: &[u8]) -> u32 {
+    u32::from_be_bytes(high_first(data))
+}
+
+/// Unpacks a big-endian [`u64`](prim@u64). See [`u8`](u8()) for short or long `data`.
+pub fn u64_be(data: &[u8]) -> u64 {
+    u64::from_be_bytes(high_first(data))
+}
+
+/// The first `N` bytes of `data`, zero-filled at the end if there are fewer.
+fn low_first<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut r = [0; N];
+    let n = data.len().min(N);
+    r[..n].copy_from_slice(&data[..n]);
+    r
+}
+
+/// The last `N` bytes of `data`, zero-filled at the start if there are fewer.
+fn high_first<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut r = [0; N];
+    let n = data.len().min(N);
+    r[N - n..].copy_from_slice(&data[data.len() - n..]);
+    r
+}
+
+/**
+Packs any integer primitive into a [`Vec`], in either byte order. Covers every width the `p*`
+functions do, plus signed types and `u128`/`i128`, and composes with [`flat`](crate::util::flat).
+
+```
+use engine::util::pack::IntoBytes;
+
+assert_eq!(0xdeadbeefu32.to_le_bytes_vec(), [0xef, 0xbe, 0xad, 0xde]);
+assert_eq!((-2i16).to_be_bytes_vec(), [0xff, 0xfe]);
+```
+*/
+pub trait IntoBytes {
+    /// The little-endian bytes of `self`.
+    fn to_le_bytes_vec(&self) -> Vec<u8>;
+    /// The big-endian bytes of `self`.
+    fn to_be_bytes_vec(&self) -> Vec<u8>;
+}
+
+macro_rules! into_bytes {
+    ($($t: ty),*) => {$(
+        impl IntoBytes for $t {
+            fn to_le_bytes_vec(&self) -> Vec<u8> {
+                self.to_le_bytes().to_vec()
+            }
+
+            fn to_be_bytes_vec(&self) -> Vec<u8> {
+                self.to_be_bytes().to_vec()
+            }
+        }
+    )*};
+}
+into_bytes!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
@@ -1,3 +1,9 @@
+use std::{
+    borrow::Borrow,
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
 /**
 When `self.len` <= FINAL:
 > Adds zeroes to the left side of `self` until `length = FINAL`.
@@ -5,32 +11,71 @@ When `self.len` <= FINAL:
 When `self.len` > FINAL:
 > Removes elements from the left side of `self` until `length = FINAL`.
 
-Consumes the input and outputs a new [u8] array.
+In both cases the elements that are kept stay in their original order. Consumes the input and
+outputs a new [u8] array. Slices are padded through a reference, without being copied into a
+[`Vec`] first.
 */
 #[trait_variant::make(Send)]
-pub trait Left: Sized + Sync + IntoIterator<Item = u8>
+pub trait Left: Sized + Sync + IntoIterator<Item: Borrow<u8>>
 where
     <Self as IntoIterator>::IntoIter: DoubleEndedIterator,
 {
     async fn pad_left<const FINAL: usize>(self) -> [u8; FINAL] {
-        async {
-            let mut r: [u8; FINAL] = [0; FINAL];
-            let mut iterator = self.into_iter();
+        async { self.pad_left_with::<FINAL>(0).await }
+    }
+
+    /// Like [`pad_left`](Left::pad_left), but pads with `fill` instead of zeroes.
+    async fn pad_left_with<const FINAL: usize>(self, fill: u8) -> [u8; FINAL] {
+        async move {
+            let mut r: [u8; FINAL] = [fill; FINAL];
+            let mut iterator = self.into_iter().rev();
 
             for index in (0..FINAL).rev() {
                 if let Some(byte) = iterator.next() {
-                    r[index] = byte;
+                    r[index] = *byte.borrow();
                 } else {
-                    r[index] = 0;
+                    r[index] = fill;
                 }
             }
 
             r
         }
     }
+
+    /**
+    Like [`pad_left`](Left::pad_left), but returns a [`PadError`] instead of removing elements when
+    `self.len` > FINAL.
+    */
+    async fn try_pad_left<const FINAL: usize>(self) -> Result<[u8; FINAL], PadError> {
+        async {
+            let bytes: Vec<u8> = self.into_iter().map(|b| *b.borrow()).collect();
+            if bytes.len() > FINAL {
+                return Err(PadError { length: bytes.len(), limit: FINAL });
+            }
+            Ok(bytes.pad_left::<FINAL>().await)
+        }
+    }
+
+    /**
+    Like [`pad_left`](Left::pad_left), but takes the length at runtime and returns a [`Vec`]
+    instead of an array.
+    */
+    async fn pad_left_to(self, len: usize) -> Vec<u8> {
+        async move {
+            let bytes: Vec<u8> = self.into_iter().map(|b| *b.borrow()).collect();
+            if bytes.len() >= len {
+                return bytes[bytes.len() - len..].to_vec();
+            }
+            let mut r = vec![0; len - bytes.len()];
+            r.extend(bytes);
+            r
+        }
+    }
 }
 impl<const INITIAL: usize> Left for [u8; INITIAL] {}
 impl Left for Vec<u8> {}
+impl Left for &[u8] {}
+impl Left for &Vec<u8> {}
 
 /**
 When `self.len` <= FINAL:
@@ -39,25 +84,74 @@ When `self.len` <= FINAL:
 When `self.len` > FINAL:
 > Removes elements from the right side of `self` until `length = FINAL`.
 
-Consumes the input and outputs a new [u8] array.
+Consumes the input and outputs a new [u8] array. Like [`Left`], also implemented for slices.
 */
 #[trait_variant::make(Send)]
-pub trait Right: Sized + Sync + IntoIterator<Item = u8> {
+pub trait Right: Sized + Sync + IntoIterator<Item: Borrow<u8>> {
     async fn pad_right<const FINAL: usize>(self) -> [u8; FINAL] {
-        async {
-            let mut r: [u8; FINAL] = [0; FINAL];
+        async { self.pad_right_with::<FINAL>(0).await }
+    }
+
+    /// Like [`pad_right`](Right::pad_right), but pads with `fill` instead of zeroes.
+    async fn pad_right_with<const FINAL: usize>(self, fill: u8) -> [u8; FINAL] {
+        async move {
+            let mut r: [u8; FINAL] = [fill; FINAL];
 
             let mut iterator = self.into_iter();
-            for index in 0..FINAL {
+            for slot in r.iter_mut() {
                 if let Some(byte) = iterator.next() {
-                    r[index] = byte
+                    *slot = *byte.borrow()
                 } else {
-                    r[index] = 0
+                    *slot = fill
                 }
             }
             r
         }
     }
+
+    /**
+    Like [`pad_right`](Right::pad_right), but returns a [`PadError`] instead of removing elements
+    when `self.len` > FINAL.
+    */
+    async fn try_pad_right<const FINAL: usize>(self) -> Result<[u8; FINAL], PadError> {
+        async {
+            let bytes: Vec<u8> = self.into_iter().map(|b| *b.borrow()).collect();
+            if bytes.len() > FINAL {
+                return Err(PadError { length: bytes.len(), limit: FINAL });
+            }
+            Ok(bytes.pad_right::<FINAL>().await)
+        }
+    }
+
+    /**
+    Like [`pad_right`](Right::pad_right), but takes the length at runtime and returns a [`Vec`]
+    instead of an array.
+    */
+    async fn pad_right_to(self, len: usize) -> Vec<u8> {
+        async move {
+            let mut r: Vec<u8> = self.into_iter().take(len).map(|b| *b.borrow()).collect();
+            r.resize(len, 0);
+            r
+        }
+    }
 }
 impl<const INITIAL: usize> Right for [u8; INITIAL] {}
 impl Right for Vec<u8> {}
+impl Right for &[u8] {}
+impl Right for &Vec<u8> {}
+
+/// Returned by [`try_pad_left`](Left::try_pad_left) and [`try_pad_right`](Right::try_pad_right)
+/// when the input does not fit.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PadError {
+    /// The length of the input.
+    pub length: usize,
+    /// The length that was asked for.
+    pub limit: usize,
+}
+impl Display for PadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes do not fit in {}", self.length, self.limit)
+    }
+}
+impl Error for PadError {}
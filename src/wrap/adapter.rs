@@ -0,0 +1,68 @@
+use crate::Engine;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/**
+Turns any `AsyncRead + AsyncWrite` stream into an [`Engine`], with the chunk
+[`timeout`](Engine::timeout) and [`repeat`](Engine::repeat) supplied at construction.
+
+```no_run
+use engine::{wrap::Adapter, Engine};
+use std::time::Duration;
+
+# #[tokio::main]
+# async fn main() {
+let (stream, _) = tokio::io::duplex(1024);
+let mut handle = Adapter::new(stream, Duration::from_millis(50)).with_repeat(2);
+handle.send_line(b"hello").await.unwrap();
+# }
+```
+*/
+pub struct Adapter<T> {
+    inner: T,
+    timeout: Duration,
+    repeat: usize,
+}
+
+impl<T> Adapter<T> {
+    /// Wraps `inner`, ending chunks after `timeout` of silence.
+    pub fn new(inner: T, timeout: Duration) -> Self {
+        Self { inner, timeout, repeat: 1 }
+    }
+
+    /// Sets the [`repeat`](Engine::repeat) count.
+    pub fn with_repeat(mut self, repeat: usize) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Returns a reference to the wrapped stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped stream.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the stream.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+delegate_read!([T: AsyncRead + Unpin] Adapter<T>, inner);
+delegate_write!([T: AsyncWrite + Unpin] Adapter<T>, inner);
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Engine for Adapter<T> {
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn repeat(&self) -> usize {
+        self.repeat
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{Engine, EngineError};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::{Error as IOError, ErrorKind, Result as IOResult},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::io::AsyncWrite;
+
+/**
+The error inside the [`InvalidInput`](ErrorKind::InvalidInput) I/O error that [`BadChars`] returns
+when a write contains a forbidden byte. `offset` counts from the start of the rejected write, which
+for `write_all` and [`send_line`](Engine::send_line) is the start of the payload.
+*/
+#[derive(Debug, PartialEq, Eq)]
+pub struct BadCharError {
+    /// The forbidden byte.
+    pub byte: u8,
+    /// Where it first occurs.
+    pub offset: usize,
+}
+impl Display for BadCharError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "payload contains bad char {:#04x} at offset {}", self.byte, self.offset)
+    }
+}
+impl Error for BadCharError {}
+
+/**
+An [`Engine`] that refuses to write data containing any of a set of forbidden bytes, such as the
+`\x00` a `strcpy` stops at or the `\n` a `gets` stops at. The whole write fails before anything is
+sent, with an [`InvalidInput`](ErrorKind::InvalidInput) error wrapping a [`BadCharError`]. Reads are
+passed through unchanged.
+
+[`send_line`](Engine::send_line) writes the payload and [`LINE_SEP`](Engine::LINE_SEP) in one go,
+so forbidding `\n` also forbids `send_line` on most engines; use `write_all` instead.
+
+```no_run
+use engine::{tcp, wrap::BadChars};
+use tokio::io::AsyncWriteExt;
+
+# #[tokio::main]
+# async fn main() {
+let mut handle = BadChars::new(tcp("www.example.com:65535").await.unwrap(), b"\x00\n");
+let error = handle.write_all(b"AAAA\x00\x11\x40").await.unwrap_err();
+assert_eq!(error.to_string(), "payload contains bad char 0x00 at offset 4");
+# }
+```
+*/
+pub struct BadChars<E> {
+    inner: E,
+    bad_chars: Vec<u8>,
+}
+
+impl<E: Engine> BadChars<E> {
+    /// Wraps `inner`, rejecting writes that contain any byte of `bad_chars`.
+    pub fn new(inner: E, bad_chars: &[u8]) -> Self {
+        Self { inner, bad_chars: bad_chars.to_vec() }
+    }
+
+    /// The forbidden bytes.
+    pub fn bad_chars(&self) -> &[u8] {
+        &self.bad_chars
+    }
+
+    /// Checks `data` without sending it, e.g. before spending a connection on it.
+    pub fn check(&self, data: &[u8]) -> Result<(), BadCharError> {
+        match data.iter().position(|byte| self.bad_chars.contains(byte)) {
+            Some(offset) => Err(BadCharError { byte: data[offset], offset }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns a reference to the wrapped engine.
+    pub fn get_ref(&self) -> &E {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped engine.
+    pub fn get_mut(&mut self) -> &mut E {
+        &mut self.inner
+    }
+
+    /// Unwraps the engine.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+delegate_read!([E: Engine] BadChars<E>, inner);
+
+impl<E: Engine> AsyncWrite for BadChars<E> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<IOResult<usize>> {
+        if let Err(error) = self.check(buf) {
+            return Poll::Ready(Err(IOError::new(ErrorKind::InvalidInput, error)));
+        }
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    delegate_flush!(inner);
+}
+
+impl<E: Engine> Engine for BadChars<E> {
+    delegate_timing!(E, inner);
+
+    async fn read_chunk_bytes_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, EngineError> {
+        self.inner.read_chunk_bytes_timeout(timeout).await
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{Engine, EngineError};
+use std::{
+    io::Result as IOResult,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+/**
+An [`Engine`] that reads the wrapped engine through a [`BufReader`]. The byte-at-a-time loops of
+[`recv_until`](Engine::recv_until) and the line readers then run over the buffer instead of
+issuing one read per byte, and [`recv_until_max`](Engine::recv_until_max) scans whole buffers at a
+time. Writes are passed through unchanged. Chunks are read from the buffer by the default
+implementations of the chunk methods, whatever the wrapped engine uses.
+
+Bytes already in the buffer have left the wrapped engine, so stack a
+[`Pushback`](super::Pushback) on the outside (`Pushback<Buffered<E>>`) rather than the inside.
+
+```no_run
+use engine::{tcp, wrap::Buffered, Engine};
+
+# #[tokio::main]
+# async fn main() {
+let mut handle = Buffered::new(tcp("www.example.com:65535").await.unwrap());
+let dump = handle.recv_until(b"END\n").await.unwrap();
+# }
+```
+*/
+pub struct Buffered<E> {
+    inner: BufReader<E>,
+}
+
+impl<E: Engine> Buffered<E> {
+    /// Wraps `inner` with a buffer of the default capacity.
+    pub fn new(inner: E) -> Self {
+        Self { inner: BufReader::new(inner) }
+    }
+
+    /// Wraps `inner` with a buffer of `capacity` bytes.
+    pub fn with_capacity(capacity: usize, inner: E) -> Self {
+        Self { inner: BufReader::with_capacity(capacity, inner) }
+    }
+
+    /// The bytes that have been read from the wrapped engine but not returned yet.
+    pub fn buffer(&self) -> &[u8] {
+        self.inner.buffer()
+    }
+
+    /// Returns a reference to the wrapped engine.
+    pub fn get_ref(&self) -> &E {
+        self.inner.get_ref()
+    }
+
+    /// Returns a mutable reference to the wrapped engine.
+    pub fn get_mut(&mut self) -> &mut E {
+        self.inner.get_mut()
+    }
+
+    /// Unwraps the engine. Any bytes still in the buffer are lost.
+    pub fn into_inner(self) -> E {
+        self.inner.into_inner()
+    }
+}
+
+delegate_read!([E: Engine] Buffered<E>, inner);
+
+impl<E: Engine> AsyncBufRead for Buffered<E> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IOResult<&[u8]>> {
+        Pin::new(&mut self.get_mut().inner).poll_fill_buf(cx)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.inner).consume(amt)
+    }
+}
+
+delegate_write!([E: Engine] Buffered<E>, inner);
+
+impl<E: Engine> Engine for Buffered<E> {
+    delegate_timing!(E, inner.get_ref());
+
+    async fn recv_until_max(&mut self, delim: &[u8], max: usize) -> Result<Vec<u8>, EngineError> {
+        let mut buf = Vec::new();
+        while !buf.ends_with(delim) {
+            if buf.len() == max {
+                return Err(EngineError::DelimiterNotFound(buf));
+            }
+            let available = self.inner.fill_buf().await?;
+            if available.is_empty() {
+                return Err(EngineError::Eof(buf));
+            }
+            let mut used = 0;
+            for &byte in available {
+                buf.push(byte);
+                used += 1;
+                if buf.ends_with(delim) || buf.len() == max {
+                    break;
+                }
+            }
+            self.inner.consume(used);
+        }
+        Ok(buf)
+    }
+}
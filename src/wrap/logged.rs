@@ -0,0 +1,139 @@
+use super::transcript::Recorder;
+use crate::{Engine, EngineError};
+use std::{
+    io::Result as IOResult,
+    path::Path,
+    pin::Pin,
+    sync::Mutex,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Which way data passed through a [`Logged`] engine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Received from the remote stream.
+    Recv,
+    /// Sent to the remote stream.
+    Send,
+}
+
+type Logger = Box<dyn Fn(Direction, &[u8]) + Send + Sync>;
+
+/**
+An [`Engine`] that hands every byte read from or written to the wrapped engine to a logging
+callback. Bytes are reported as the underlying reads and writes complete, so a single logical
+read may be reported in several pieces. Chunks are read by the wrapped engine's own
+[`read_chunk_bytes_timeout`](Engine::read_chunk_bytes_timeout) and reported in one piece, along
+with whatever an error carries.
+
+```no_run
+use engine::{tcp, wrap::{Direction, Logged}, Engine};
+
+# #[tokio::main]
+# async fn main() {
+let mut handle = Logged::new(tcp("www.example.com:65535").await.unwrap(), |direction, data| {
+    let arrow = if direction == Direction::Recv { "<<" } else { ">>" };
+    eprintln!("{} {:?}", arrow, String::from_utf8_lossy(data));
+});
+handle.send_line(b"hello").await.unwrap();
+# }
+```
+*/
+pub struct Logged<E> {
+    inner: E,
+    logger: Logger,
+}
+
+impl<E: Engine> Logged<E> {
+    /// Wraps `inner`, reporting its traffic to `logger`.
+    pub fn new(inner: E, logger: impl Fn(Direction, &[u8]) + Send + Sync + 'static) -> Self {
+        Self { inner, logger: Box::new(logger) }
+    }
+
+    /**
+    Wraps `inner`, recording its traffic to a transcript file at `path`, which is created or
+    truncated. Each line of the file is one [`Entry`](super::Entry); the last one is written when
+    the engine is dropped.
+    */
+    pub fn record(inner: E, path: impl AsRef<Path>) -> IOResult<Self> {
+        let recorder = Mutex::new(Recorder::create(path)?);
+        Ok(Self::new(inner, move |direction, data| recorder.lock().unwrap().log(direction, data)))
+    }
+
+    /// Replaces the logging callback.
+    pub fn set_logger(&mut self, logger: impl Fn(Direction, &[u8]) + Send + Sync + 'static) {
+        self.logger = Box::new(logger);
+    }
+
+    /// Returns a reference to the wrapped engine.
+    pub fn get_ref(&self) -> &E {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped engine.
+    pub fn get_mut(&mut self) -> &mut E {
+        &mut self.inner
+    }
+
+    /// Unwraps the engine, discarding the logger.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+
+    /// Reports the data of a read that bypassed [`poll_read`](AsyncRead::poll_read).
+    fn log_read(&self, read: &Result<Vec<u8>, EngineError>) {
+        let data = match read {
+            Ok(data) | Err(EngineError::Eof(data)) => data,
+            Err(_) => return,
+        };
+        if !data.is_empty() {
+            (self.logger)(Direction::Recv, data);
+        }
+    }
+}
+
+impl<E: Engine> AsyncRead for Logged<E> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IOResult<()>> {
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        if buf.filled().len() > before {
+            (self.logger)(Direction::Recv, &buf.filled()[before..]);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<E: Engine> AsyncWrite for Logged<E> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<IOResult<usize>> {
+        let n = ready!(Pin::new(&mut self.inner).poll_write(cx, buf))?;
+        if n > 0 {
+            (self.logger)(Direction::Send, &buf[..n]);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    delegate_flush!(inner);
+}
+
+impl<E: Engine> Engine for Logged<E> {
+    delegate_timing!(E, inner);
+
+    async fn read_chunk_bytes_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, EngineError> {
+        let read = self.inner.read_chunk_bytes_timeout(timeout).await;
+        self.log_read(&read);
+        read
+    }
+}
@@ -0,0 +1,264 @@
+/*!
+Wrappers that layer extra behaviour on top of an existing [`Engine`](crate::Engine). Every wrapper
+is itself an [`Engine`](crate::Engine), so they can be stacked. [`Adapter`] is the starting point
+for streams that are not an [`Engine`](crate::Engine) yet.
+
+Wrappers take their timing from the engine they wrap. [`BadChars`] and [`Logged`], which pass
+reads through unchanged, also forward
+[`read_chunk_bytes_timeout`](crate::Engine::read_chunk_bytes_timeout), which
+[`read_chunk`](crate::Engine::read_chunk) and its variants go through, so that a `Logged<Udp>`
+still reads one datagram per chunk. The others read through a
+buffer or timing of their own ([`Pushback`], [`Buffered`], [`Tuned`], [`ReadHalf`]), so wrapping an
+engine in them resets it to the default. Every other method of a wrapper is the default too, e.g.
+the scanning [`recv_until_max`](crate::Engine::recv_until_max) of [`Buffered`] only applies with
+[`Buffered`] on the outside.
+*/
+
+/// Implements `AsyncRead` for a wrapper by passing every read straight through to `self.$inner`.
+macro_rules! delegate_read {
+    ([$($generics:tt)*] $wrapper:ty, $inner:ident) => {
+        impl<$($generics)*> tokio::io::AsyncRead for $wrapper {
+            fn poll_read(
+                mut self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                buf: &mut tokio::io::ReadBuf<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                std::pin::Pin::new(&mut self.$inner).poll_read(cx, buf)
+            }
+        }
+    };
+}
+
+/// Implements `AsyncWrite` for a wrapper by passing every write straight through to `self.$inner`.
+macro_rules! delegate_write {
+    ([$($generics:tt)*] $wrapper:ty, $inner:ident) => {
+        impl<$($generics)*> tokio::io::AsyncWrite for $wrapper {
+            fn poll_write(
+                mut self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+                buf: &[u8],
+            ) -> std::task::Poll<std::io::Result<usize>> {
+                std::pin::Pin::new(&mut self.$inner).poll_write(cx, buf)
+            }
+
+            delegate_flush!($inner);
+        }
+    };
+}
+
+/// The `poll_flush` and `poll_shutdown` of an `AsyncWrite` impl that only changes `poll_write`.
+macro_rules! delegate_flush {
+    ($inner:ident) => {
+        fn poll_flush(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::pin::Pin::new(&mut self.$inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::pin::Pin::new(&mut self.$inner).poll_shutdown(cx)
+        }
+    };
+}
+
+/**
+The constants and timing methods of an `Engine` impl for a wrapper around an `$engine`, taken from
+the engine that `self.$inner` evaluates to.
+*/
+macro_rules! delegate_timing {
+    ($engine:ident, $($inner:tt)+) => {
+        const TIMEOUT: std::time::Duration = $engine::TIMEOUT;
+        const REPEAT: usize = $engine::REPEAT;
+        const LINE_SEP: &'static [u8] = $engine::LINE_SEP;
+
+        fn timeout(&self) -> std::time::Duration {
+            self.$($inner)+.timeout()
+        }
+
+        fn repeat(&self) -> usize {
+            self.$($inner)+.repeat()
+        }
+
+        fn drain_timeout(&self) -> std::time::Duration {
+            self.$($inner)+.drain_timeout()
+        }
+    };
+}
+
+mod adapter;
+pub use adapter::Adapter;
+
+mod bad_chars;
+pub use bad_chars::{BadCharError, BadChars};
+
+mod buffered;
+pub use buffered::Buffered;
+
+mod logged;
+pub use logged::{Direction, Logged};
+
+mod pushback;
+pub use pushback::Pushback;
+
+mod split;
+pub(crate) use split::split_engine;
+pub use split::{ReadHalf, WriteHalf};
+
+mod transcript;
+pub use transcript::{read_transcript, Entry, ParseEntryError};
+
+mod tuned;
+pub use tuned::Tuned;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        read_transcript, Adapter, BadCharError, BadChars, Buffered, Direction, Logged, Pushback,
+        Tuned,
+    };
+    use crate::{Engine, EngineError};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use tokio::{
+        io::{duplex, AsyncWriteExt},
+        join,
+        time::sleep,
+    };
+
+    #[tokio::test]
+    async fn adapter() {
+        let (local, mut remote) = duplex(64);
+        let mut local = Adapter::new(local, Duration::from_millis(20)).with_repeat(2);
+        assert_eq!((local.timeout(), local.repeat()), (Duration::from_millis(20), 2));
+
+        remote.write_all(b"> ").await.unwrap();
+        assert_eq!(local.recv_until(b"> ").await.unwrap(), b"> ");
+    }
+
+    #[tokio::test]
+    async fn tuned() {
+        let (local, mut remote) = duplex(64);
+        let mut local = Tuned::new(local).with_timeout(Duration::from_millis(20)).with_repeat(3);
+        assert_eq!((local.timeout(), local.repeat()), (Duration::from_millis(20), 3));
+
+        let slow = async {
+            remote.write_all(b"still").await.unwrap();
+            sleep(Duration::from_millis(30)).await;
+            remote.write_all(b" here").await.unwrap();
+        };
+        let (_, chunk) = join!(slow, local.read_chunk());
+        assert_eq!(chunk.unwrap(), "still here");
+    }
+
+    #[tokio::test]
+    async fn logged() {
+        let (local, mut remote) = duplex(64);
+        let transcript = Arc::new(Mutex::new(Vec::new()));
+        let log = transcript.clone();
+        let mut local = Logged::new(local, move |direction, data: &[u8]| {
+            log.lock().unwrap().push((direction, data.to_vec()));
+        });
+
+        remote.write_all(b"> ").await.unwrap();
+        local.recv_until(b"> ").await.unwrap();
+        local.send_line(b"ls").await.unwrap();
+        assert_eq!(
+            *transcript.lock().unwrap(),
+            [
+                (Direction::Recv, b">".to_vec()),
+                (Direction::Recv, b" ".to_vec()),
+                (Direction::Send, b"ls\n".to_vec())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn recorded() {
+        let path = std::env::temp_dir().join(format!("engine-record-{}", std::process::id()));
+        let (local, mut remote) = duplex(64);
+        let mut local = Logged::record(local, &path).unwrap();
+
+        remote.write_all(b"Name:\t\x01\\").await.unwrap();
+        local.recv_until(b"\\").await.unwrap();
+        local.send_line(b"admin").await.unwrap();
+        drop(local);
+
+        let transcript = std::fs::read_to_string(&path).unwrap();
+        let entries = read_transcript(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<_> = transcript.lines().map(|l| l.split_once(' ').unwrap().1).collect();
+        assert_eq!(lines, [r"< Name:\t\x01\\", r"> admin\n"]);
+        assert_eq!(entries[0].data, b"Name:\t\x01\\");
+        assert_eq!(
+            (entries[1].direction, &entries[1].data[..]),
+            (Direction::Send, &b"admin\n"[..])
+        );
+        assert!(entries[0].time <= entries[1].time);
+    }
+
+    #[tokio::test]
+    async fn pushback() {
+        let (local, mut remote) = duplex(64);
+        let mut local = Pushback::new(local);
+        remote.write_all(b"\x03abc").await.unwrap();
+
+        let header = local.recv_n(2).await.unwrap();
+        local.unread(&header[1..]);
+        local.unread(&header[..1]);
+        assert_eq!(local.recv_n(4).await.unwrap(), b"\x03abc");
+    }
+
+    #[tokio::test]
+    async fn peeked() {
+        let (local, mut remote) = duplex(64);
+        let mut local = Pushback::new(local);
+        remote.write_all(b"\x03abcdef").await.unwrap();
+
+        let length = local.peek(1).await.unwrap()[0] as usize;
+        assert_eq!(local.peek(3).await.unwrap(), b"\x03ab");
+        assert_eq!(local.recv_n(1 + length).await.unwrap(), b"\x03abc");
+
+        drop(remote);
+        assert!(local.peek(4).await.is_err());
+        assert_eq!(local.recv_all().await.unwrap(), b"def");
+    }
+
+    #[tokio::test]
+    async fn bad_chars() {
+        let (local, mut remote) = duplex(64);
+        let mut local = BadChars::new(local, b"\x00\n");
+        assert_eq!(local.check(b"ok"), Ok(()));
+
+        let error = local.write_all(b"AAAA\x11\x00").await.unwrap_err();
+        let inner = error.get_ref().unwrap().downcast_ref::<BadCharError>().unwrap();
+        assert_eq!(*inner, BadCharError { byte: 0, offset: 5 });
+        assert_eq!(error.to_string(), "payload contains bad char 0x00 at offset 5");
+        assert!(local.send_line(b"ls").await.is_err());
+
+        local.write_all(b"AAAA\x11").await.unwrap();
+        assert_eq!(remote.recv_n(5).await.unwrap(), b"AAAA\x11");
+    }
+
+    #[tokio::test]
+    async fn buffered() {
+        let (local, mut remote) = duplex(64);
+        let mut local = Pushback::new(Buffered::with_capacity(8, local));
+        remote.write_all(b"one\ntwo\n> three").await.unwrap();
+
+        assert_eq!(local.get_mut().recv_line().await.unwrap(), b"one");
+        assert_eq!(local.get_ref().buffer(), b"two\n");
+        assert_eq!(local.get_mut().recv_until(b"\n> ").await.unwrap(), b"two\n> ");
+        let error = local.get_mut().recv_until_max(b"\n", 3).await.unwrap_err();
+        assert!(matches!(error, EngineError::DelimiterNotFound(partial) if partial == b"thr"));
+
+        local.unread(b"thr");
+        drop(remote);
+        assert_eq!(local.recv_all().await.unwrap(), b"three");
+    }
+}
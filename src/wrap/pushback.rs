@@ -0,0 +1,101 @@
+use crate::Engine;
+use std::{
+    io::{Error as IOError, ErrorKind, Result as IOResult},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/**
+An [`Engine`] with an "unget" buffer. Bytes handed to [`unread`](Pushback::unread) are returned by
+the next reads before anything new is read from the wrapped engine, so every read primitive on
+[`Engine`] sees them.
+
+```no_run
+use engine::{tcp, wrap::Pushback, Engine};
+
+# #[tokio::main]
+# async fn main() {
+let mut handle = Pushback::new(tcp("www.example.com:65535").await.unwrap());
+let header = handle.recv_n(4).await.unwrap();
+if header[0] != 0xff {
+    // Not a length prefix after all -- put it back.
+    handle.unread(&header);
+}
+
+// Or look at the length byte first, and leave it in the stream.
+let length = handle.peek(1).await.unwrap()[0] as usize;
+let message = handle.recv_n(1 + length).await.unwrap();
+# }
+```
+*/
+pub struct Pushback<E> {
+    inner: E,
+    buffer: Vec<u8>,
+}
+
+impl<E: Engine> Pushback<E> {
+    /// Wraps `inner` with an empty unget buffer.
+    pub fn new(inner: E) -> Self {
+        Self { inner, buffer: Vec::new() }
+    }
+
+    /// Pushes `data` back so that it is returned, in order, before any data already pushed back.
+    pub fn unread(&mut self, data: &[u8]) {
+        self.buffer.splice(0..0, data.iter().copied());
+    }
+
+    /**
+    Returns the next `n` bytes without consuming them: they are read into the unget buffer, so the
+    next reads return them again. Fails with [`UnexpectedEof`](ErrorKind::UnexpectedEof) if the
+    stream ends first, in which case the bytes that did arrive stay in the buffer.
+    */
+    pub async fn peek(&mut self, n: usize) -> Result<Vec<u8>, IOError> {
+        let mut chunk = [0; 1024];
+        while self.buffer.len() < n {
+            let want = (n - self.buffer.len()).min(chunk.len());
+            match self.inner.read(&mut chunk[..want]).await? {
+                0 => return Err(ErrorKind::UnexpectedEof.into()),
+                read => self.buffer.extend_from_slice(&chunk[..read]),
+            }
+        }
+        Ok(self.buffer[..n].to_vec())
+    }
+
+    /// Returns a reference to the wrapped engine.
+    pub fn get_ref(&self) -> &E {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped engine.
+    pub fn get_mut(&mut self) -> &mut E {
+        &mut self.inner
+    }
+
+    /// Unwraps the engine. Any bytes still in the unget buffer are lost.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: Engine> AsyncRead for Pushback<E> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IOResult<()>> {
+        if self.buffer.is_empty() {
+            return Pin::new(&mut self.inner).poll_read(cx, buf);
+        }
+        let n = self.buffer.len().min(buf.remaining());
+        buf.put_slice(&self.buffer[..n]);
+        self.buffer.drain(..n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+delegate_write!([E: Engine] Pushback<E>, inner);
+
+impl<E: Engine> Engine for Pushback<E> {
+    delegate_timing!(E, inner);
+}
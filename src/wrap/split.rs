@@ -0,0 +1,122 @@
+use crate::Engine;
+use std::{
+    io::{Error as IOError, ErrorKind, Result as IOResult},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::io::{self, AsyncRead, AsyncWrite, ReadBuf};
+
+/// The [`timeout`](Engine::timeout), [`repeat`](Engine::repeat) and
+/// [`drain_timeout`](Engine::drain_timeout) of the engine a half was split from.
+#[derive(Clone, Copy)]
+struct Timing {
+    timeout: Duration,
+    repeat: usize,
+    drain_timeout: Duration,
+}
+
+/**
+The reading half of an engine, returned by [`Engine::split`]. It is itself an [`Engine`] with the
+timing of the original, so every read method works on it, although with the default
+implementations rather than any the original overrides; writes fail with
+[`Unsupported`](ErrorKind::Unsupported).
+*/
+pub struct ReadHalf<E> {
+    inner: io::ReadHalf<E>,
+    timing: Timing,
+}
+
+/**
+The writing half of an engine, returned by [`Engine::split`]. It is itself an [`Engine`], so every
+send method works on it; reads fail with [`Unsupported`](ErrorKind::Unsupported).
+*/
+pub struct WriteHalf<E> {
+    inner: io::WriteHalf<E>,
+    timing: Timing,
+}
+
+pub(crate) fn split_engine<E: Engine>(engine: E) -> (ReadHalf<E>, WriteHalf<E>) {
+    let timing = Timing {
+        timeout: engine.timeout(),
+        repeat: engine.repeat(),
+        drain_timeout: engine.drain_timeout(),
+    };
+    let (reader, writer) = io::split(engine);
+    (ReadHalf { inner: reader, timing }, WriteHalf { inner: writer, timing })
+}
+
+impl<E: Engine> ReadHalf<E> {
+    /// Puts the engine back together. Panics if `writer` was split from a different engine.
+    pub fn unsplit(self, writer: WriteHalf<E>) -> E {
+        self.inner.unsplit(writer.inner)
+    }
+}
+
+fn unsupported(what: &str) -> IOError {
+    IOError::new(ErrorKind::Unsupported, format!("cannot {} this half of an engine", what))
+}
+
+delegate_read!([E: Engine] ReadHalf<E>, inner);
+
+impl<E: Engine> AsyncWrite for ReadHalf<E> {
+    fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, _: &[u8]) -> Poll<IOResult<usize>> {
+        Poll::Ready(Err(unsupported("write to")))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IOResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<IOResult<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<E: Engine> AsyncRead for WriteHalf<E> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        _: &mut ReadBuf<'_>,
+    ) -> Poll<IOResult<()>> {
+        Poll::Ready(Err(unsupported("read from")))
+    }
+}
+
+delegate_write!([E: Engine] WriteHalf<E>, inner);
+
+impl<E: Engine> Engine for ReadHalf<E> {
+    const TIMEOUT: Duration = E::TIMEOUT;
+    const REPEAT: usize = E::REPEAT;
+    const LINE_SEP: &'static [u8] = E::LINE_SEP;
+
+    fn timeout(&self) -> Duration {
+        self.timing.timeout
+    }
+
+    fn repeat(&self) -> usize {
+        self.timing.repeat
+    }
+
+    fn drain_timeout(&self) -> Duration {
+        self.timing.drain_timeout
+    }
+}
+
+impl<E: Engine> Engine for WriteHalf<E> {
+    const TIMEOUT: Duration = E::TIMEOUT;
+    const REPEAT: usize = E::REPEAT;
+    const LINE_SEP: &'static [u8] = E::LINE_SEP;
+
+    fn timeout(&self) -> Duration {
+        self.timing.timeout
+    }
+
+    fn repeat(&self) -> usize {
+        self.timing.repeat
+    }
+
+    fn drain_timeout(&self) -> Duration {
+        self.timing.drain_timeout
+    }
+}
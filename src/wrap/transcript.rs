@@ -0,0 +1,159 @@
+use super::Direction;
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::{read_to_string, File},
+    io::{Error as IOError, ErrorKind, Result as IOResult, Write},
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/**
+One line of a transcript written by [`Logged::record`](super::Logged::record): data that went one
+way, and when it started to.
+
+Its [`Display`] form is the line itself: the seconds since recording started, `>` for sent or `<`
+for received data, and then the data. Backslashes, line breaks and other non-printable bytes in the
+data are escaped (`\\`, `\n`, `\x00`), so each entry stays on one line:
+
+```text
+0.000512 < Welcome!\nName:
+0.480210 > admin\n
+```
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// How long after the start of the recording the data passed through.
+    pub time: Duration,
+    /// Which way the data went.
+    pub direction: Direction,
+    /// The data.
+    pub data: Vec<u8>,
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let arrow = match self.direction {
+            Direction::Send => '>',
+            Direction::Recv => '<',
+        };
+        write!(f, "{}.{:06} {} ", self.time.as_secs(), self.time.subsec_micros(), arrow)?;
+        for byte in &self.data {
+            match byte {
+                b'\\' => write!(f, "\\\\")?,
+                b'\n' => write!(f, "\\n")?,
+                b'\r' => write!(f, "\\r")?,
+                b'\t' => write!(f, "\\t")?,
+                b' '..=b'~' => write!(f, "{}", *byte as char)?,
+                _ => write!(f, "\\x{:02x}", byte)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Entry {
+    type Err = ParseEntryError;
+
+    /// Parses a line in the [`Display`] form of an [`Entry`].
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let (time, rest) = line.split_once(' ').ok_or(ParseEntryError)?;
+        let time = Duration::try_from_secs_f64(time.parse().map_err(|_| ParseEntryError)?)
+            .map_err(|_| ParseEntryError)?;
+        let direction = match rest.as_bytes().first() {
+            Some(b'>') => Direction::Send,
+            Some(b'<') => Direction::Recv,
+            _ => return Err(ParseEntryError),
+        };
+        let escaped = rest[1..].strip_prefix(' ').unwrap_or(&rest[1..]);
+
+        let (mut data, mut bytes) = (Vec::new(), escaped.bytes());
+        while let Some(byte) = bytes.next() {
+            if byte != b'\\' {
+                data.push(byte);
+                continue;
+            }
+            data.push(match bytes.next().ok_or(ParseEntryError)? {
+                b'\\' => b'\\',
+                b'n' => b'\n',
+                b'r' => b'\r',
+                b't' => b'\t',
+                b'x' => {
+                    let digits = [
+                        bytes.next().ok_or(ParseEntryError)?,
+                        bytes.next().ok_or(ParseEntryError)?,
+                    ];
+                    let digits = std::str::from_utf8(&digits).map_err(|_| ParseEntryError)?;
+                    u8::from_str_radix(digits, 16).map_err(|_| ParseEntryError)?
+                }
+                _ => return Err(ParseEntryError),
+            });
+        }
+        Ok(Self { time, direction, data })
+    }
+}
+
+/// Returned when a line is not a valid [`Entry`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseEntryError;
+impl Display for ParseEntryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid transcript entry")
+    }
+}
+impl Error for ParseEntryError {}
+
+/// Reads every [`Entry`] of the transcript at `path`, skipping blank lines.
+pub fn read_transcript(path: impl AsRef<Path>) -> IOResult<Vec<Entry>> {
+    read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            line.parse()
+                .map_err(|e| IOError::new(ErrorKind::InvalidData, format!("line {}: {}", i + 1, e)))
+        })
+        .collect()
+}
+
+/**
+Writes what a [`Logged`](super::Logged) engine reports to a transcript file. Consecutive pieces of
+data that go the same way are merged into one [`Entry`], which is written once the direction
+changes or the recorder is dropped.
+*/
+pub(super) struct Recorder {
+    file: File,
+    start: Instant,
+    pending: Option<Entry>,
+}
+
+impl Recorder {
+    pub(super) fn create(path: impl AsRef<Path>) -> IOResult<Self> {
+        Ok(Self { file: File::create(path)?, start: Instant::now(), pending: None })
+    }
+
+    pub(super) fn log(&mut self, direction: Direction, data: &[u8]) {
+        match &mut self.pending {
+            Some(entry) if entry.direction == direction => entry.data.extend_from_slice(data),
+            _ => {
+                self.flush();
+                let time = self.start.elapsed();
+                self.pending = Some(Entry { time, direction, data: data.to_vec() });
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(entry) = self.pending.take() {
+            // A failing transcript must not break the connection it records.
+            let _ = writeln!(self.file, "{}", entry);
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
@@ -0,0 +1,78 @@
+use crate::Engine;
+use std::time::Duration;
+
+/**
+An [`Engine`] whose [`timeout`](Engine::timeout) and [`repeat`](Engine::repeat) are chosen at
+runtime instead of being fixed by the [`TIMEOUT`](Engine::TIMEOUT) and [`REPEAT`](Engine::REPEAT)
+constants of the wrapped type. Chunks are read with the default implementations of the chunk
+methods, so that they use the tuned values.
+
+```no_run
+use engine::{tcp, wrap::Tuned};
+use std::time::Duration;
+
+# #[tokio::main]
+# async fn main() {
+let mut handle = Tuned::new(tcp("www.example.com:65535").await.unwrap())
+    .with_timeout(Duration::from_millis(200))
+    .with_repeat(2);
+# }
+```
+*/
+pub struct Tuned<E> {
+    inner: E,
+    timeout: Duration,
+    repeat: usize,
+}
+
+impl<E: Engine> Tuned<E> {
+    /// Wraps `inner`, starting from its current [`timeout`](Engine::timeout) and
+    /// [`repeat`](Engine::repeat).
+    pub fn new(inner: E) -> Self {
+        Self { timeout: inner.timeout(), repeat: inner.repeat(), inner }
+    }
+
+    /// Sets the chunk timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the [`repeat`](Engine::repeat) count.
+    pub fn with_repeat(mut self, repeat: usize) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Returns a reference to the wrapped engine.
+    pub fn get_ref(&self) -> &E {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped engine.
+    pub fn get_mut(&mut self) -> &mut E {
+        &mut self.inner
+    }
+
+    /// Unwraps the engine, discarding the tuned values.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+delegate_read!([E: Engine] Tuned<E>, inner);
+delegate_write!([E: Engine] Tuned<E>, inner);
+
+impl<E: Engine> Engine for Tuned<E> {
+    const TIMEOUT: Duration = E::TIMEOUT;
+    const REPEAT: usize = E::REPEAT;
+    const LINE_SEP: &'static [u8] = E::LINE_SEP;
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn repeat(&self) -> usize {
+        self.repeat
+    }
+}